@@ -1,5 +1,8 @@
 use {
-    anchor_lang::{system_program, AccountDeserialize, InstructionData, ToAccountMetas},
+    anchor_lang::{
+        system_program, AccountDeserialize, AnchorSerialize, Discriminator, InstructionData,
+        ToAccountMetas,
+    },
     anchor_spl::{
         associated_token::spl_associated_token_account, token::spl_token, token_2022::spl_token_2022
     },
@@ -11,8 +14,19 @@ use {
     },
     spl_token_2022::{instruction::initialize_account, state::Account},
     std::convert::TryInto,
+    vrgda_exp::{
+        math::ONE,
+        state::{
+            AuctionParams, BatchPurchase, CommitRevealConfig, EditionConfig, EditionDropParams,
+            MetadataMode, MintMode, PurchaseIntent, RateLimits, VrgdaV0, VRGDA, VRGDA_V0_SPACE,
+        },
+    },
 };
 
+/// Compute limit the purchase helpers request per purchase. Kept at the default
+/// budget so a pricing regression fails the tests instead of being absorbed.
+pub const BUY_COMPUTE_UNITS: u32 = 200_000;
+
 pub fn create_account(
     svm: &mut litesvm::LiteSVM,
     payer: &Keypair,
@@ -77,6 +91,15 @@ pub fn create_token_account(
 }
 
 pub fn init_mint_account(svm: &mut litesvm::LiteSVM, payer: &Keypair, token_mint: &Keypair) {
+    init_mint_account_with_authority(svm, payer, token_mint, &token_mint.pubkey());
+}
+
+pub fn init_mint_account_with_authority(
+    svm: &mut litesvm::LiteSVM,
+    payer: &Keypair,
+    token_mint: &Keypair,
+    mint_authority: &Pubkey,
+) {
     let mint_rent = svm.minimum_balance_for_rent_exemption(spl_token_2022::state::Mint::LEN);
     let mint_tx = Transaction::new_signed_with_payer(
         &[
@@ -90,7 +113,97 @@ pub fn init_mint_account(svm: &mut litesvm::LiteSVM, payer: &Keypair, token_mint
             spl_token_2022::instruction::initialize_mint(
                 &spl_token_2022::id(),
                 &token_mint.pubkey(),
+                mint_authority,
+                None,
+                6,
+            )
+            .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer, &token_mint],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(mint_tx).unwrap();
+}
+
+pub fn init_mint_account_with_transfer_fee(
+    svm: &mut litesvm::LiteSVM,
+    payer: &Keypair,
+    token_mint: &Keypair,
+    mint_authority: &Pubkey,
+    transfer_fee_basis_points: u16,
+    maximum_fee: u64,
+) {
+    let mint_len = spl_token_2022::extension::ExtensionType::try_calculate_account_len::<
+        spl_token_2022::state::Mint,
+    >(&[spl_token_2022::extension::ExtensionType::TransferFeeConfig])
+    .unwrap();
+    let mint_rent = svm.minimum_balance_for_rent_exemption(mint_len);
+    let mint_tx = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &token_mint.pubkey(),
+                mint_rent,
+                mint_len.try_into().unwrap(),
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::extension::transfer_fee::instruction::initialize_transfer_fee_config(
+                &spl_token_2022::id(),
+                &token_mint.pubkey(),
+                None,
+                None,
+                transfer_fee_basis_points,
+                maximum_fee,
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint(
+                &spl_token_2022::id(),
+                &token_mint.pubkey(),
+                mint_authority,
+                None,
+                6,
+            )
+            .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer, &token_mint],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(mint_tx).unwrap();
+}
+
+pub fn init_mint_account_with_metadata_pointer(
+    svm: &mut litesvm::LiteSVM,
+    payer: &Keypair,
+    token_mint: &Keypair,
+    mint_authority: &Pubkey,
+) {
+    let mint_len = spl_token_2022::extension::ExtensionType::try_calculate_account_len::<
+        spl_token_2022::state::Mint,
+    >(&[spl_token_2022::extension::ExtensionType::MetadataPointer])
+    .unwrap();
+    let mint_rent = svm.minimum_balance_for_rent_exemption(mint_len);
+    let mint_tx = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &token_mint.pubkey(),
+                mint_rent,
+                mint_len.try_into().unwrap(),
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::extension::metadata_pointer::instruction::initialize(
+                &spl_token_2022::id(),
+                &token_mint.pubkey(),
+                Some(*mint_authority),
+                Some(token_mint.pubkey()),
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint(
+                &spl_token_2022::id(),
                 &token_mint.pubkey(),
+                mint_authority,
                 None,
                 6,
             )
@@ -176,12 +289,36 @@ pub fn initialize_ata(
     svm.send_transaction(create_ata_tx)
 }
 
+/// Address of the first auction, `auction_id` 0, of `mint` by `authority`.
 pub fn get_vrgda_address(program_id: Pubkey, mint: &Pubkey, authority: &Pubkey) -> Pubkey {
-    let seeds = [b"vrgda", mint.as_ref(), authority.as_ref()];
+    get_auction_address(program_id, mint, authority, 0)
+}
+
+pub fn get_auction_address(
+    program_id: Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    auction_id: u64,
+) -> Pubkey {
+    let seeds = [
+        b"vrgda".as_ref(),
+        mint.as_ref(),
+        authority.as_ref(),
+        &auction_id.to_le_bytes(),
+    ];
     let (address, _bump) = Pubkey::find_program_address(&seeds, &program_id);
     address
 }
 
+/// Address of an auction created by the first release, which derived it
+/// without `auction_id`.
+pub fn get_legacy_vrgda_address(mint: &Pubkey, authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"vrgda".as_ref(), mint.as_ref(), authority.as_ref()],
+        &vrgda_exp::ID,
+    )
+}
+
 pub fn initialize_vrgda_testing_accounts(
     svm: &mut litesvm::LiteSVM,
     vrgda_pda: &Pubkey,
@@ -201,6 +338,66 @@ pub fn initialize_vrgda_testing_accounts(
     symbol: &str,
     uri: &str,
     buy_window: i64,
+) {
+    initialize_auction(
+        svm,
+        vrgda_pda,
+        vrgda_sol_ata,
+        vrgda_mint_ata,
+        payer,
+        mint,
+        wsol_mint,
+        authority,
+        metadata,
+        AuctionParams {
+            target_price,
+            decay_constant_percent,
+            vrgda_start_timestamp,
+            total_supply,
+            r,
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            uri: uri.to_string(),
+            ..default_auction_params()
+        },
+    );
+}
+
+/// Parameters of the auction most tests run, a pre-minted supply of one
+/// billion with Metaplex metadata and none of the optional features.
+pub fn default_auction_params() -> AuctionParams {
+    AuctionParams {
+        auction_id: 0,
+        target_price: 4 * ONE,
+        decay_constant_percent: 50,
+        vrgda_start_timestamp: 0,
+        total_supply: 1_000_000_000,
+        r: 1_000_000,
+        name: "vrgda token".to_string(),
+        symbol: "VRGDA".to_string(),
+        uri: "https://example.com/metadata.json".to_string(),
+        payees: Vec::new(),
+        vesting: None,
+        reserve: None,
+        soft_cap: None,
+        mint_mode: MintMode::PreMint,
+        metadata_mode: MetadataMode::Metaplex,
+    }
+}
+
+/// Creates the auction at `vrgda_pda` with `params`, along with the sale and
+/// quote mints and the authority's quote ATA when they don't exist yet.
+pub fn initialize_auction(
+    svm: &mut litesvm::LiteSVM,
+    vrgda_pda: &Pubkey,
+    vrgda_sol_ata: &Pubkey,
+    vrgda_mint_ata: &Pubkey,
+    payer: &Keypair,
+    mint: &Keypair,
+    wsol_mint: &Keypair,
+    authority: &Keypair,
+    metadata: Pubkey,
+    params: AuctionParams,
 ) {
     // let vrgda_address = get_vrgda_address(vrgda_exp::ID, &mint.pubkey(), &authority.pubkey());
     // let rent_exemption = svm.minimum_balance_for_rent_exemption(vrgda_exp::state::VRGDA::INIT_SPACE);
@@ -235,8 +432,11 @@ pub fn initialize_vrgda_testing_accounts(
     // ).unwrap();
 
     println!("VRGDA account created at: {:?}", vrgda_pda);
-    // initialize mint account
-    let _ = init_mint_account(svm, payer, mint);
+    // initialize mint account, the authority hands mint authority over to the VRGDA PDA.
+    // Tests that need mint extensions create the mint themselves beforehand.
+    if svm.get_account(&mint.pubkey()).is_none() {
+        init_mint_account_with_authority(svm, payer, mint, &authority.pubkey());
+    }
 
     println!("Mint account initialized at: {:?}", mint.pubkey());
 
@@ -258,8 +458,10 @@ pub fn initialize_vrgda_testing_accounts(
             &spl_token_2022::ID,
         )
     );
-    // initialize wsol mint account
-    let _ = init_mint_account(svm, payer, wsol_mint);
+    // initialize wsol mint account, unless an earlier auction already did
+    if svm.get_account(&wsol_mint.pubkey()).is_none() {
+        init_mint_account(svm, payer, wsol_mint);
+    }
 
     println!("WSOL Mint account initialized at: {:?}", wsol_mint.pubkey());
 
@@ -287,31 +489,48 @@ pub fn initialize_vrgda_testing_accounts(
     //     &[&authority.pubkey()],
     // ).unwrap();
 
+    // Auctions of a mint whose authority went to an earlier auction are funded
+    // from the authority's own tokens.
+    let authority_token_account =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            &authority.pubkey(),
+            &mint.pubkey(),
+            &spl_token_2022::ID,
+        );
+
     let ix_accounts = vrgda_exp::accounts::Initialize {
         authority: authority.pubkey(),
         vrgda: *vrgda_pda,
         vrgda_vault: *vrgda_mint_ata,
         mint: mint.pubkey(),
+        authority_token_account: svm
+            .get_account(&authority_token_account)
+            .map(|_| authority_token_account),
         wsol_mint: wsol_mint.pubkey(),
         vrgda_sol_ata: *vrgda_sol_ata,
-        metadata,
-        metadata_program: mpl_token_metadata::ID,
+        proceeds_vault: (!params.payees.is_empty() || params.soft_cap.is_some())
+            .then(|| get_proceeds_vault_address(vrgda_pda, &wsol_mint.pubkey())),
+        reserve_escrow: params
+            .reserve
+            .map(|_| get_reserve_escrow_address(vrgda_pda)),
+        reserve_vault: params.reserve.map(|_| {
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &get_reserve_escrow_address(vrgda_pda),
+                &mint.pubkey(),
+                &spl_token_2022::ID,
+            )
+        }),
+        metadata: (params.metadata_mode == MetadataMode::Metaplex).then_some(metadata),
+        metadata_program: (params.metadata_mode == MetadataMode::Metaplex)
+            .then_some(mpl_token_metadata::ID),
         token_program: spl_token_2022::ID,
         associated_token_program: spl_associated_token_account::ID,
         system_program: solana_sdk::system_program::ID,
         rent: solana_sdk::sysvar::rent::ID,
     };
 
-    let ix = vrgda_exp::instruction::InitializeVrgda {
-        target_price,
-        decay_constant_percent,
-        vrgda_start_timestamp,
-        total_supply,
-        r,
-        name: name.to_string(),
-        symbol: symbol.to_string(),
-        uri: uri.to_string(),
-    };
+    let total_supply = params.total_supply;
+    let ix = vrgda_exp::instruction::InitializeVrgda { params };
 
     let ix = Instruction {
         program_id: vrgda_exp::ID,
@@ -327,19 +546,175 @@ pub fn initialize_vrgda_testing_accounts(
     );
     svm.send_transaction(transaction).unwrap();
     svm.get_account(vrgda_pda).unwrap();
-    // mint some tokens to the VRGDA vault
-    let _ = mint_tokens(svm, payer, mint, *vrgda_mint_ata, total_supply);
     println!(
         "Minted {} tokens to VRGDA vault at: {:?}",
         total_supply, vrgda_mint_ata
     );
 }
 
+/// A fresh SVM with the VRGDA and Metaplex programs loaded, and the keys and
+/// addresses of auction 0 of `mint` by `authority`. Tests call `initialize`,
+/// then take the fields apart.
+pub struct AuctionFixture {
+    pub svm: litesvm::LiteSVM,
+    pub payer: Keypair,
+    pub mint: Keypair,
+    pub wsol_mint: Keypair,
+    pub authority: Keypair,
+    pub buyer: Keypair,
+    pub metadata: Pubkey,
+    pub vrgda_pda: Pubkey,
+    pub vrgda_mint_ata: Pubkey,
+    pub vrgda_sol_ata: Pubkey,
+}
+
+impl Default for AuctionFixture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AuctionFixture {
+    pub fn new() -> Self {
+        let mut svm = litesvm::LiteSVM::new();
+        svm.add_program_from_file(vrgda_exp::ID, "../target/deploy/vrgda_exp.so")
+            .expect("Failed to load VRGDA program");
+        svm.add_program_from_file(
+            mpl_token_metadata::ID,
+            "../mpl/metaplex_token_metadata_program.so",
+        )
+        .expect("Failed to load Metaplex Metadata program");
+
+        let mint = Keypair::new();
+        let wsol_mint = Keypair::new();
+        let authority = Keypair::new();
+        let vrgda_pda = get_vrgda_address(vrgda_exp::ID, &mint.pubkey(), &authority.pubkey());
+        AuctionFixture {
+            svm,
+            payer: Keypair::new(),
+            metadata: get_metadata_pda(&mint.pubkey(), &mpl_token_metadata::ID).0,
+            vrgda_mint_ata:
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &vrgda_pda,
+                    &mint.pubkey(),
+                    &spl_token_2022::ID,
+                ),
+            vrgda_sol_ata:
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &authority.pubkey(),
+                    &wsol_mint.pubkey(),
+                    &spl_token_2022::ID,
+                ),
+            vrgda_pda,
+            mint,
+            wsol_mint,
+            authority,
+            buyer: Keypair::new(),
+        }
+    }
+
+    /// Creates the auction, `params.auction_id` has to stay 0.
+    pub fn initialize(&mut self, params: AuctionParams) {
+        initialize_auction(
+            &mut self.svm,
+            &self.vrgda_pda,
+            &self.vrgda_sol_ata,
+            &self.vrgda_mint_ata,
+            &self.payer,
+            &self.mint,
+            &self.wsol_mint,
+            &self.authority,
+            self.metadata,
+            params,
+        );
+    }
+}
+
+pub fn initialize_vrgda_with_mint(
+    svm: &mut litesvm::LiteSVM,
+    authority: &Keypair,
+    wsol_mint: &Pubkey,
+    target_price: u128,
+    total_supply: u64,
+    name: &str,
+    symbol: &str,
+    uri: &str,
+    decimals: u8,
+    metadata_mode: MetadataMode,
+    auction_id: u64,
+) -> TransactionResult {
+    let mint = get_sale_mint_address(&authority.pubkey(), symbol);
+    let vrgda_pda = get_auction_address(vrgda_exp::ID, &mint, &authority.pubkey(), auction_id);
+    let metadata = get_metadata_pda(&mint, &mpl_token_metadata::ID).0;
+
+    let ix_accounts = vrgda_exp::accounts::InitializeWithMint {
+        authority: authority.pubkey(),
+        mint,
+        vrgda: vrgda_pda,
+        vrgda_vault: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &vrgda_pda,
+            &mint,
+            &spl_token_2022::ID,
+        ),
+        vrgda_sol_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &authority.pubkey(),
+            wsol_mint,
+            &spl_token_2022::ID,
+        ),
+        proceeds_vault: None,
+        reserve_escrow: None,
+        reserve_vault: None,
+        wsol_mint: *wsol_mint,
+        metadata: (metadata_mode == MetadataMode::Metaplex).then_some(metadata),
+        metadata_program: (metadata_mode == MetadataMode::Metaplex)
+            .then_some(mpl_token_metadata::ID),
+        system_program: system_program::ID,
+        token_program: spl_token_2022::ID,
+        associated_token_program: spl_associated_token_account::ID,
+        rent: solana_sdk::sysvar::rent::ID,
+    };
+
+    let ix = vrgda_exp::instruction::InitializeVrgdaWithMint {
+        params: AuctionParams {
+            auction_id,
+            target_price,
+            decay_constant_percent: 50,
+            vrgda_start_timestamp: 0,
+            total_supply,
+            r: 1_000_000,
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            uri: uri.to_string(),
+            payees: Vec::new(),
+            vesting: None,
+            reserve: None,
+            soft_cap: None,
+            mint_mode: MintMode::PreMint,
+            metadata_mode,
+        },
+        decimals,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: ix.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&authority.pubkey()),
+        &[authority],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
 pub fn buy_tokens(
     svm: &mut litesvm::LiteSVM,
     admin: &Keypair,
     buyer: &Keypair,
-    vrgda_authority: &Keypair,
+    payout: &Keypair,
     vrgda_pda: &Pubkey,
     vrgda_sol_ata: &Pubkey,
     vrgda_mint_ata: &Pubkey,
@@ -347,9 +722,70 @@ pub fn buy_tokens(
     wsol_mint: &Keypair,
     amount: u64,
 ) {
+    buy_tokens_for(
+        svm,
+        admin,
+        buyer,
+        &buyer.pubkey(),
+        payout,
+        vrgda_pda,
+        vrgda_sol_ata,
+        vrgda_mint_ata,
+        mint,
+        wsol_mint,
+        amount,
+        false,
+    )
+    .unwrap();
+}
+
+/// Like `buy_tokens`, for auctions that lock purchases in a vesting escrow.
+pub fn buy_vested_tokens(
+    svm: &mut litesvm::LiteSVM,
+    admin: &Keypair,
+    buyer: &Keypair,
+    payout: &Keypair,
+    vrgda_pda: &Pubkey,
+    vrgda_sol_ata: &Pubkey,
+    vrgda_mint_ata: &Pubkey,
+    mint: &Keypair,
+    wsol_mint: &Keypair,
+    amount: u64,
+) -> TransactionResult {
+    buy_tokens_for(
+        svm,
+        admin,
+        buyer,
+        &buyer.pubkey(),
+        payout,
+        vrgda_pda,
+        vrgda_sol_ata,
+        vrgda_mint_ata,
+        mint,
+        wsol_mint,
+        amount,
+        true,
+    )
+}
+
+/// Like `buy_tokens`, but `buyer` pays and `recipient` receives the tokens.
+pub fn buy_tokens_for(
+    svm: &mut litesvm::LiteSVM,
+    admin: &Keypair,
+    buyer: &Keypair,
+    recipient: &Pubkey,
+    payout: &Keypair,
+    vrgda_pda: &Pubkey,
+    vrgda_sol_ata: &Pubkey,
+    vrgda_mint_ata: &Pubkey,
+    mint: &Keypair,
+    wsol_mint: &Keypair,
+    amount: u64,
+    vesting: bool,
+) -> TransactionResult {
     svm.airdrop(&buyer.pubkey(), 100_000_000_000_000).unwrap();
 
-    let _ = initialize_ata(svm, buyer, mint.pubkey(), buyer.pubkey());
+    let _ = initialize_ata(svm, buyer, mint.pubkey(), *recipient);
 
     let _ = initialize_ata(svm, buyer, wsol_mint.pubkey(), buyer.pubkey());
 
@@ -365,8 +801,8 @@ pub fn buy_tokens(
         "Buyer ATA should have been created"
     );
 
-    let buyer_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
-        &buyer.pubkey(),
+    let recipient_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        recipient,
         &mint.pubkey(),
         &spl_token_2022::ID,
     );
@@ -383,14 +819,25 @@ pub fn buy_tokens(
 
     let ix_accounts = vrgda_exp::accounts::Buy {
         buyer: buyer.pubkey(),
+        recipient: *recipient,
         vrgda: *vrgda_pda,
         mint: mint.pubkey(),
         wsol_mint: wsol_mint.pubkey(),
         buyer_wsol_ata: buyer_wsol_ata,
-        buyer_ata,
+        recipient_ata,
         vrgda_vault: *vrgda_mint_ata,
         vrgda_sol_ata: *vrgda_sol_ata,
-        authority: vrgda_authority.pubkey(),
+        proceeds_vault: get_auction_proceeds_vault(svm, vrgda_pda, &wsol_mint.pubkey()),
+        buyer_record: Some(get_buyer_record_address(vrgda_pda, recipient)),
+        vesting_escrow: vesting.then(|| get_vesting_escrow_address(vrgda_pda, recipient)),
+        vesting_vault: vesting.then(|| {
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &get_vesting_escrow_address(vrgda_pda, recipient),
+                &mint.pubkey(),
+                &spl_token_2022::ID,
+            )
+        }),
+        payout: payout.pubkey(),
         token_program: spl_token_2022::ID,
         associated_token_program: spl_associated_token_account::ID,
         system_program: system_program::ID,
@@ -404,7 +851,7 @@ pub fn buy_tokens(
     };
 
     let compute_budget_ix: Instruction =
-        ComputeBudgetInstruction::set_compute_unit_limit(1_400_000);
+        ComputeBudgetInstruction::set_compute_unit_limit(BUY_COMPUTE_UNITS);
 
     let transaction = Transaction::new_signed_with_payer(
         &[compute_budget_ix, instruction],
@@ -412,7 +859,1530 @@ pub fn buy_tokens(
         &[buyer],
         svm.latest_blockhash(),
     );
-    svm.send_transaction(transaction).unwrap();
+    svm.send_transaction(transaction)
+}
+
+/// Funds `buyer` and sends one `buy_batch` that delivers to the recipients'
+/// ATAs, which are created first. Purchases index into `recipients`.
+pub fn buy_batch(
+    svm: &mut litesvm::LiteSVM,
+    admin: &Keypair,
+    buyer: &Keypair,
+    payout: &Keypair,
+    vrgda_pda: &Pubkey,
+    mint: &Keypair,
+    wsol_mint: &Keypair,
+    recipients: &[Pubkey],
+    purchases: Vec<BatchPurchase>,
+) -> TransactionResult {
+    svm.airdrop(&buyer.pubkey(), 100_000_000_000_000).unwrap();
+
+    let _ = initialize_ata(svm, buyer, wsol_mint.pubkey(), buyer.pubkey());
+    let buyer_wsol_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &buyer.pubkey(),
+        &wsol_mint.pubkey(),
+        &spl_token_2022::ID,
+    );
+    let _ = mint_tokens(
+        svm,
+        admin,
+        wsol_mint,
+        buyer_wsol_ata,
+        10_000_000_000_000_000,
+    );
+
+    let recipient_atas: Vec<Pubkey> = recipients
+        .iter()
+        .map(|recipient| {
+            let _ = initialize_ata(svm, buyer, mint.pubkey(), *recipient);
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                recipient,
+                &mint.pubkey(),
+                &spl_token_2022::ID,
+            )
+        })
+        .collect();
+
+    let ix_accounts = vrgda_exp::accounts::BuyBatch {
+        buyer: buyer.pubkey(),
+        vrgda: *vrgda_pda,
+        mint: mint.pubkey(),
+        wsol_mint: wsol_mint.pubkey(),
+        buyer_wsol_ata,
+        vrgda_vault: spl_associated_token_account::get_associated_token_address_with_program_id(
+            vrgda_pda,
+            &mint.pubkey(),
+            &spl_token_2022::ID,
+        ),
+        vrgda_sol_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &payout.pubkey(),
+            &wsol_mint.pubkey(),
+            &spl_token_2022::ID,
+        ),
+        proceeds_vault: get_auction_proceeds_vault(svm, vrgda_pda, &wsol_mint.pubkey()),
+        payout: payout.pubkey(),
+        token_program: spl_token_2022::ID,
+        associated_token_program: spl_associated_token_account::ID,
+        system_program: system_program::ID,
+    };
+
+    let mut accounts = ix_accounts.to_account_metas(None);
+    accounts.extend(
+        recipient_atas
+            .iter()
+            .map(|ata| solana_sdk::instruction::AccountMeta::new(*ata, false)),
+    );
+
+    let compute_budget_ix: Instruction = ComputeBudgetInstruction::set_compute_unit_limit(
+        BUY_COMPUTE_UNITS * purchases.len() as u32,
+    );
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts,
+        data: vrgda_exp::instruction::BuyBatch { purchases }.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[compute_budget_ix, instruction],
+        Some(&buyer.pubkey()),
+        &[buyer],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+/// Funds `owner` with WSOL and places a limit order for `amount` tokens.
+pub fn place_limit_order(
+    svm: &mut litesvm::LiteSVM,
+    admin: &Keypair,
+    owner: &Keypair,
+    vrgda_pda: &Pubkey,
+    mint: &Pubkey,
+    wsol_mint: &Keypair,
+    order_id: u64,
+    amount: u64,
+    max_price: u64,
+    tip: u64,
+) -> TransactionResult {
+    svm.airdrop(&owner.pubkey(), 100_000_000_000_000).unwrap();
+
+    let _ = initialize_ata(svm, owner, wsol_mint.pubkey(), owner.pubkey());
+    let owner_wsol_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &owner.pubkey(),
+        &wsol_mint.pubkey(),
+        &spl_token_2022::ID,
+    );
+    let _ = mint_tokens(
+        svm,
+        admin,
+        wsol_mint,
+        owner_wsol_ata,
+        10_000_000_000_000_000,
+    );
+
+    let limit_order = get_limit_order_address(vrgda_pda, &owner.pubkey(), order_id);
+    let ix_accounts = vrgda_exp::accounts::PlaceLimitOrder {
+        owner: owner.pubkey(),
+        vrgda: *vrgda_pda,
+        limit_order,
+        order_escrow: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &limit_order,
+            &wsol_mint.pubkey(),
+            &spl_token_2022::ID,
+        ),
+        mint: *mint,
+        wsol_mint: wsol_mint.pubkey(),
+        owner_wsol_ata,
+        owner_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &owner.pubkey(),
+            mint,
+            &spl_token_2022::ID,
+        ),
+        token_program: spl_token_2022::ID,
+        associated_token_program: spl_associated_token_account::ID,
+        system_program: system_program::ID,
+    };
+
+    let ix = vrgda_exp::instruction::PlaceLimitOrder {
+        order_id,
+        amount,
+        max_price,
+        tip,
+    };
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: ix.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&owner.pubkey()),
+        &[owner],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn cancel_limit_order(
+    svm: &mut litesvm::LiteSVM,
+    owner: &Keypair,
+    vrgda_pda: &Pubkey,
+    wsol_mint: &Pubkey,
+    order_id: u64,
+) -> TransactionResult {
+    let limit_order = get_limit_order_address(vrgda_pda, &owner.pubkey(), order_id);
+    let ix_accounts = vrgda_exp::accounts::CancelLimitOrder {
+        owner: owner.pubkey(),
+        limit_order,
+        order_escrow: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &limit_order,
+            wsol_mint,
+            &spl_token_2022::ID,
+        ),
+        wsol_mint: *wsol_mint,
+        owner_wsol_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &owner.pubkey(),
+            wsol_mint,
+            &spl_token_2022::ID,
+        ),
+        token_program: spl_token_2022::ID,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::CancelLimitOrder {}.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&owner.pubkey()),
+        &[owner],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn execute_limit_order(
+    svm: &mut litesvm::LiteSVM,
+    cranker: &Keypair,
+    owner: &Pubkey,
+    payout: &Pubkey,
+    vrgda_pda: &Pubkey,
+    mint: &Pubkey,
+    wsol_mint: &Pubkey,
+    order_id: u64,
+) -> TransactionResult {
+    let ata = |authority: &Pubkey, mint: &Pubkey| {
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            authority,
+            mint,
+            &spl_token_2022::ID,
+        )
+    };
+    let limit_order = get_limit_order_address(vrgda_pda, owner, order_id);
+    let ix_accounts = vrgda_exp::accounts::ExecuteLimitOrder {
+        cranker: cranker.pubkey(),
+        owner: *owner,
+        vrgda: *vrgda_pda,
+        limit_order,
+        order_escrow: ata(&limit_order, wsol_mint),
+        mint: *mint,
+        wsol_mint: *wsol_mint,
+        owner_ata: ata(owner, mint),
+        owner_wsol_ata: ata(owner, wsol_mint),
+        cranker_wsol_ata: ata(&cranker.pubkey(), wsol_mint),
+        vrgda_vault: ata(vrgda_pda, mint),
+        vrgda_sol_ata: ata(payout, wsol_mint),
+        proceeds_vault: get_auction_proceeds_vault(svm, vrgda_pda, wsol_mint),
+        payout: *payout,
+        token_program: spl_token_2022::ID,
+        associated_token_program: spl_associated_token_account::ID,
+        system_program: system_program::ID,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::ExecuteLimitOrder {}.data(),
+    };
+
+    let compute_budget_ix: Instruction =
+        ComputeBudgetInstruction::set_compute_unit_limit(BUY_COMPUTE_UNITS);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[compute_budget_ix, instruction],
+        Some(&cranker.pubkey()),
+        &[cranker],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+/// Funds `owner` with WSOL and creates a DCA order buying `amount_per_slice`
+/// tokens every `interval_seconds`.
+pub fn create_dca_order(
+    svm: &mut litesvm::LiteSVM,
+    admin: &Keypair,
+    owner: &Keypair,
+    vrgda_pda: &Pubkey,
+    mint: &Pubkey,
+    wsol_mint: &Keypair,
+    order_id: u64,
+    amount_per_slice: u64,
+    max_price_per_slice: u64,
+    interval_seconds: i64,
+    slices: u64,
+) -> TransactionResult {
+    svm.airdrop(&owner.pubkey(), 100_000_000_000_000).unwrap();
+
+    let _ = initialize_ata(svm, owner, wsol_mint.pubkey(), owner.pubkey());
+    let owner_wsol_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &owner.pubkey(),
+        &wsol_mint.pubkey(),
+        &spl_token_2022::ID,
+    );
+    let _ = mint_tokens(
+        svm,
+        admin,
+        wsol_mint,
+        owner_wsol_ata,
+        10_000_000_000_000_000,
+    );
+
+    let dca_order = get_dca_order_address(vrgda_pda, &owner.pubkey(), order_id);
+    let ix_accounts = vrgda_exp::accounts::CreateDcaOrder {
+        owner: owner.pubkey(),
+        vrgda: *vrgda_pda,
+        dca_order,
+        order_escrow: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &dca_order,
+            &wsol_mint.pubkey(),
+            &spl_token_2022::ID,
+        ),
+        mint: *mint,
+        wsol_mint: wsol_mint.pubkey(),
+        owner_wsol_ata,
+        owner_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &owner.pubkey(),
+            mint,
+            &spl_token_2022::ID,
+        ),
+        token_program: spl_token_2022::ID,
+        associated_token_program: spl_associated_token_account::ID,
+        system_program: system_program::ID,
+    };
+
+    let ix = vrgda_exp::instruction::CreateDcaOrder {
+        order_id,
+        amount_per_slice,
+        max_price_per_slice,
+        interval_seconds,
+        slices,
+    };
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: ix.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&owner.pubkey()),
+        &[owner],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn cancel_dca_order(
+    svm: &mut litesvm::LiteSVM,
+    owner: &Keypair,
+    vrgda_pda: &Pubkey,
+    wsol_mint: &Pubkey,
+    order_id: u64,
+) -> TransactionResult {
+    let dca_order = get_dca_order_address(vrgda_pda, &owner.pubkey(), order_id);
+    let ix_accounts = vrgda_exp::accounts::CancelDcaOrder {
+        owner: owner.pubkey(),
+        dca_order,
+        order_escrow: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &dca_order,
+            wsol_mint,
+            &spl_token_2022::ID,
+        ),
+        wsol_mint: *wsol_mint,
+        owner_wsol_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &owner.pubkey(),
+            wsol_mint,
+            &spl_token_2022::ID,
+        ),
+        token_program: spl_token_2022::ID,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::CancelDcaOrder {}.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&owner.pubkey()),
+        &[owner],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn execute_dca(
+    svm: &mut litesvm::LiteSVM,
+    cranker: &Keypair,
+    owner: &Pubkey,
+    payout: &Pubkey,
+    vrgda_pda: &Pubkey,
+    mint: &Pubkey,
+    wsol_mint: &Pubkey,
+    order_id: u64,
+) -> TransactionResult {
+    let ata = |authority: &Pubkey, mint: &Pubkey| {
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            authority,
+            mint,
+            &spl_token_2022::ID,
+        )
+    };
+    let dca_order = get_dca_order_address(vrgda_pda, owner, order_id);
+    let ix_accounts = vrgda_exp::accounts::ExecuteDca {
+        cranker: cranker.pubkey(),
+        owner: *owner,
+        vrgda: *vrgda_pda,
+        dca_order,
+        order_escrow: ata(&dca_order, wsol_mint),
+        mint: *mint,
+        wsol_mint: *wsol_mint,
+        owner_ata: ata(owner, mint),
+        owner_wsol_ata: ata(owner, wsol_mint),
+        vrgda_vault: ata(vrgda_pda, mint),
+        vrgda_sol_ata: ata(payout, wsol_mint),
+        proceeds_vault: get_auction_proceeds_vault(svm, vrgda_pda, wsol_mint),
+        payout: *payout,
+        token_program: spl_token_2022::ID,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::ExecuteDca {}.data(),
+    };
+
+    let compute_budget_ix: Instruction =
+        ComputeBudgetInstruction::set_compute_unit_limit(BUY_COMPUTE_UNITS);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[compute_budget_ix, instruction],
+        Some(&cranker.pubkey()),
+        &[cranker],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+/// Gives `buyer` WSOL and approves the auction PDA to spend `allowance` of it,
+/// with `admin` paying the fees so the buyer never needs SOL.
+pub fn approve_intent_allowance(
+    svm: &mut litesvm::LiteSVM,
+    admin: &Keypair,
+    buyer: &Keypair,
+    vrgda_pda: &Pubkey,
+    wsol_mint: &Keypair,
+    allowance: u64,
+) {
+    let _ = initialize_ata(svm, admin, wsol_mint.pubkey(), buyer.pubkey());
+    let buyer_wsol_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &buyer.pubkey(),
+        &wsol_mint.pubkey(),
+        &spl_token_2022::ID,
+    );
+    let _ = mint_tokens(
+        svm,
+        admin,
+        wsol_mint,
+        buyer_wsol_ata,
+        10_000_000_000_000_000,
+    );
+
+    let approve_ix = spl_token_2022::instruction::approve(
+        &spl_token_2022::ID,
+        &buyer_wsol_ata,
+        vrgda_pda,
+        &buyer.pubkey(),
+        &[],
+        allowance,
+    )
+    .unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[approve_ix],
+        Some(&admin.pubkey()),
+        &[admin, buyer],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction).unwrap();
+}
+
+/// Submits `intent` from `relayer`, preceded by the ed25519 precompile
+/// instruction carrying `signer`'s signature over it.
+pub fn buy_with_intent(
+    svm: &mut litesvm::LiteSVM,
+    relayer: &Keypair,
+    buyer: &Pubkey,
+    signer: &Keypair,
+    payout: &Pubkey,
+    mint: &Pubkey,
+    wsol_mint: &Pubkey,
+    intent: PurchaseIntent,
+) -> TransactionResult {
+    let message = intent.message().unwrap();
+    let signature: [u8; 64] = signer.sign_message(&message).as_ref().try_into().unwrap();
+    let ed25519_ix = solana_sdk::ed25519_instruction::new_ed25519_instruction_with_signature(
+        &message,
+        &signature,
+        &signer.pubkey().to_bytes(),
+    );
+
+    let ata = |authority: &Pubkey, mint: &Pubkey| {
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            authority,
+            mint,
+            &spl_token_2022::ID,
+        )
+    };
+    let vrgda_pda = intent.vrgda;
+    let ix_accounts = vrgda_exp::accounts::BuyWithIntent {
+        relayer: relayer.pubkey(),
+        buyer: *buyer,
+        vrgda: vrgda_pda,
+        intent_nonce: get_intent_nonce_address(&vrgda_pda, buyer, intent.nonce),
+        mint: *mint,
+        wsol_mint: *wsol_mint,
+        buyer_wsol_ata: ata(buyer, wsol_mint),
+        buyer_ata: ata(buyer, mint),
+        vrgda_vault: ata(&vrgda_pda, mint),
+        vrgda_sol_ata: ata(payout, wsol_mint),
+        proceeds_vault: get_auction_proceeds_vault(svm, &vrgda_pda, wsol_mint),
+        payout: *payout,
+        instructions: solana_sdk::sysvar::instructions::ID,
+        token_program: spl_token_2022::ID,
+        associated_token_program: spl_associated_token_account::ID,
+        system_program: system_program::ID,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::BuyWithIntent { intent }.data(),
+    };
+
+    let compute_budget_ix: Instruction =
+        ComputeBudgetInstruction::set_compute_unit_limit(BUY_COMPUTE_UNITS);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[compute_budget_ix, ed25519_ix, instruction],
+        Some(&relayer.pubkey()),
+        &[relayer],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn update_params(
+    svm: &mut litesvm::LiteSVM,
+    authority: &Keypair,
+    vrgda_pda: &Pubkey,
+    target_price: u128,
+    decay_constant_percent: u64,
+    r: u64,
+) -> TransactionResult {
+    let ix_accounts = vrgda_exp::accounts::UpdateParams {
+        authority: authority.pubkey(),
+        vrgda: *vrgda_pda,
+    };
+
+    let ix = vrgda_exp::instruction::UpdateParams {
+        target_price,
+        decay_constant_percent,
+        r,
+    };
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: ix.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&authority.pubkey()),
+        &[authority],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+/// Sends `quote`, the receipt comes back as the transaction's return data.
+pub fn quote(
+    svm: &mut litesvm::LiteSVM,
+    payer: &Keypair,
+    vrgda_pda: &Pubkey,
+    wsol_mint: &Pubkey,
+    amount: u64,
+) -> TransactionResult {
+    let ix_accounts = vrgda_exp::accounts::Quote {
+        vrgda: *vrgda_pda,
+        wsol_mint: *wsol_mint,
+        token_program: spl_token_2022::ID,
+    };
+
+    let ix = vrgda_exp::instruction::Quote { amount };
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: ix.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+/// Buys through the mock caller program into `owner`'s caller wallet, which has
+/// to be funded with SOL and quote tokens.
+pub fn caller_buy(
+    svm: &mut litesvm::LiteSVM,
+    owner: &Keypair,
+    payout: &Pubkey,
+    vrgda_pda: &Pubkey,
+    mint: &Pubkey,
+    wsol_mint: &Pubkey,
+    amount: u64,
+    max_cost: u64,
+) -> TransactionResult {
+    let wallet = get_caller_wallet_address(&owner.pubkey());
+    let ix_accounts = vrgda_caller::accounts::Buy {
+        owner: owner.pubkey(),
+        wallet,
+        vrgda: *vrgda_pda,
+        mint: *mint,
+        wsol_mint: *wsol_mint,
+        wallet_wsol_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &wallet,
+            wsol_mint,
+            &spl_token_2022::ID,
+        ),
+        wallet_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &wallet,
+            mint,
+            &spl_token_2022::ID,
+        ),
+        vrgda_vault: spl_associated_token_account::get_associated_token_address_with_program_id(
+            vrgda_pda,
+            mint,
+            &spl_token_2022::ID,
+        ),
+        vrgda_sol_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            payout,
+            wsol_mint,
+            &spl_token_2022::ID,
+        ),
+        proceeds_vault: get_auction_proceeds_vault(svm, vrgda_pda, wsol_mint),
+        buyer_record: Some(get_buyer_record_address(vrgda_pda, &wallet)),
+        payout: *payout,
+        token_program: spl_token_2022::ID,
+        associated_token_program: spl_associated_token_account::ID,
+        system_program: system_program::ID,
+        rent: solana_sdk::sysvar::rent::ID,
+        vrgda_program: vrgda_exp::ID,
+    };
+
+    let ix = vrgda_caller::instruction::Buy { amount, max_cost };
+    let instruction = Instruction {
+        program_id: vrgda_caller::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: ix.data(),
+    };
+
+    let compute_budget_ix: Instruction =
+        ComputeBudgetInstruction::set_compute_unit_limit(BUY_COMPUTE_UNITS);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[compute_budget_ix, instruction],
+        Some(&owner.pubkey()),
+        &[owner],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn set_payout(
+    svm: &mut litesvm::LiteSVM,
+    authority: &Keypair,
+    vrgda_pda: &Pubkey,
+    payout: &Pubkey,
+) -> TransactionResult {
+    let ix_accounts = vrgda_exp::accounts::SetPayout {
+        authority: authority.pubkey(),
+        vrgda: *vrgda_pda,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::SetPayout { payout: *payout }.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&authority.pubkey()),
+        &[authority],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn set_rate_limits(
+    svm: &mut litesvm::LiteSVM,
+    authority: &Keypair,
+    vrgda_pda: &Pubkey,
+    rate_limits: RateLimits,
+) -> TransactionResult {
+    let ix_accounts = vrgda_exp::accounts::SetRateLimits {
+        authority: authority.pubkey(),
+        vrgda: *vrgda_pda,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::SetRateLimits { rate_limits }.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&authority.pubkey()),
+        &[authority],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn migrate(
+    svm: &mut litesvm::LiteSVM,
+    authority: &Keypair,
+    vrgda_pda: &Pubkey,
+) -> TransactionResult {
+    let ix_accounts = vrgda_exp::accounts::Migrate {
+        authority: authority.pubkey(),
+        vrgda: *vrgda_pda,
+        system_program: system_program::ID,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::Migrate {}.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&authority.pubkey()),
+        &[authority],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+/// Writes `auction` at `vrgda_pda` the way the first release stored it: the
+/// `VRGDA` discriminator followed by the version 0 fields, with only the rent
+/// for that size.
+pub fn write_v0_auction(svm: &mut litesvm::LiteSVM, vrgda_pda: &Pubkey, auction: &VrgdaV0) {
+    let mut data = VRGDA::DISCRIMINATOR.to_vec();
+    auction.serialize(&mut data).unwrap();
+    assert_eq!(data.len(), 8 + VRGDA_V0_SPACE);
+
+    let account = solana_sdk::account::Account {
+        lamports: svm.minimum_balance_for_rent_exemption(data.len()),
+        data,
+        owner: vrgda_exp::ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+    svm.set_account(*vrgda_pda, account).unwrap();
+}
+
+pub fn set_commit_reveal(
+    svm: &mut litesvm::LiteSVM,
+    authority: &Keypair,
+    vrgda_pda: &Pubkey,
+    commit_reveal: Option<CommitRevealConfig>,
+) -> TransactionResult {
+    let ix_accounts = vrgda_exp::accounts::SetCommitReveal {
+        authority: authority.pubkey(),
+        vrgda: *vrgda_pda,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::SetCommitReveal { commit_reveal }.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&authority.pubkey()),
+        &[authority],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn commit_purchase(
+    svm: &mut litesvm::LiteSVM,
+    admin: &Keypair,
+    buyer: &Keypair,
+    vrgda_pda: &Pubkey,
+    wsol_mint: &Keypair,
+    commitment: [u8; 32],
+    deposit: u64,
+) -> TransactionResult {
+    svm.airdrop(&buyer.pubkey(), 100_000_000_000_000).unwrap();
+
+    let _ = initialize_ata(svm, buyer, wsol_mint.pubkey(), buyer.pubkey());
+    let buyer_wsol_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+        &buyer.pubkey(),
+        &wsol_mint.pubkey(),
+        &spl_token_2022::ID,
+    );
+    let _ = mint_tokens(
+        svm,
+        admin,
+        wsol_mint,
+        buyer_wsol_ata,
+        10_000_000_000_000_000,
+    );
+
+    let purchase_commit = get_purchase_commit_address(vrgda_pda, &buyer.pubkey());
+    let ix_accounts = vrgda_exp::accounts::Commit {
+        buyer: buyer.pubkey(),
+        vrgda: *vrgda_pda,
+        purchase_commit,
+        commit_escrow: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &purchase_commit,
+            &wsol_mint.pubkey(),
+            &spl_token_2022::ID,
+        ),
+        wsol_mint: wsol_mint.pubkey(),
+        buyer_wsol_ata,
+        token_program: spl_token_2022::ID,
+        associated_token_program: spl_associated_token_account::ID,
+        system_program: system_program::ID,
+    };
+
+    let ix = vrgda_exp::instruction::Commit {
+        commitment,
+        deposit,
+    };
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: ix.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&buyer.pubkey()),
+        &[buyer],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn reveal_purchase(
+    svm: &mut litesvm::LiteSVM,
+    buyer: &Keypair,
+    payout: &Pubkey,
+    vrgda_pda: &Pubkey,
+    mint: &Pubkey,
+    wsol_mint: &Pubkey,
+    amount: u64,
+    max_cost: u64,
+    salt: [u8; 32],
+) -> TransactionResult {
+    let ata = |authority: &Pubkey, mint: &Pubkey| {
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            authority,
+            mint,
+            &spl_token_2022::ID,
+        )
+    };
+    let purchase_commit = get_purchase_commit_address(vrgda_pda, &buyer.pubkey());
+    let ix_accounts = vrgda_exp::accounts::Reveal {
+        buyer: buyer.pubkey(),
+        vrgda: *vrgda_pda,
+        purchase_commit,
+        commit_escrow: ata(&purchase_commit, wsol_mint),
+        mint: *mint,
+        wsol_mint: *wsol_mint,
+        buyer_wsol_ata: ata(&buyer.pubkey(), wsol_mint),
+        buyer_ata: ata(&buyer.pubkey(), mint),
+        vrgda_vault: ata(vrgda_pda, mint),
+        vrgda_sol_ata: ata(payout, wsol_mint),
+        proceeds_vault: get_auction_proceeds_vault(svm, vrgda_pda, wsol_mint),
+        payout: *payout,
+        token_program: spl_token_2022::ID,
+        associated_token_program: spl_associated_token_account::ID,
+        system_program: system_program::ID,
+    };
+
+    let ix = vrgda_exp::instruction::Reveal {
+        amount,
+        max_cost,
+        salt,
+    };
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: ix.data(),
+    };
+
+    let compute_budget_ix: Instruction =
+        ComputeBudgetInstruction::set_compute_unit_limit(BUY_COMPUTE_UNITS);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[compute_budget_ix, instruction],
+        Some(&buyer.pubkey()),
+        &[buyer],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn reclaim_commit(
+    svm: &mut litesvm::LiteSVM,
+    buyer: &Keypair,
+    vrgda_pda: &Pubkey,
+    wsol_mint: &Pubkey,
+) -> TransactionResult {
+    let purchase_commit = get_purchase_commit_address(vrgda_pda, &buyer.pubkey());
+    let ix_accounts = vrgda_exp::accounts::ReclaimCommit {
+        buyer: buyer.pubkey(),
+        purchase_commit,
+        commit_escrow: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &purchase_commit,
+            wsol_mint,
+            &spl_token_2022::ID,
+        ),
+        wsol_mint: *wsol_mint,
+        buyer_wsol_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &buyer.pubkey(),
+            wsol_mint,
+            &spl_token_2022::ID,
+        ),
+        token_program: spl_token_2022::ID,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::ReclaimCommit {}.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&buyer.pubkey()),
+        &[buyer],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn initialize_edition_drop(
+    svm: &mut litesvm::LiteSVM,
+    authority: &Keypair,
+    drop_mint: &Pubkey,
+    wsol_mint: &Pubkey,
+    target_price: u128,
+    max_editions: u64,
+    edition: EditionConfig,
+    auction_id: u64,
+) -> TransactionResult {
+    let vrgda_pda = get_auction_address(vrgda_exp::ID, drop_mint, &authority.pubkey(), auction_id);
+
+    let ix_accounts = vrgda_exp::accounts::InitializeEditionDrop {
+        authority: authority.pubkey(),
+        vrgda: vrgda_pda,
+        mint: *drop_mint,
+        vrgda_sol_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &authority.pubkey(),
+            wsol_mint,
+            &spl_token_2022::ID,
+        ),
+        proceeds_vault: None,
+        wsol_mint: *wsol_mint,
+        system_program: system_program::ID,
+        token_program: spl_token_2022::ID,
+        associated_token_program: spl_associated_token_account::ID,
+    };
+
+    let ix = vrgda_exp::instruction::InitializeEditionDrop {
+        params: EditionDropParams {
+            auction_id,
+            target_price,
+            decay_constant_percent: 50,
+            vrgda_start_timestamp: 0,
+            max_editions,
+            r: 1,
+            payees: Vec::new(),
+            edition,
+        },
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: ix.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&authority.pubkey()),
+        &[authority],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+/// Buys edition `index` (zero based) of a drop without a collection.
+pub fn buy_edition(
+    svm: &mut litesvm::LiteSVM,
+    buyer: &Keypair,
+    vrgda_pda: &Pubkey,
+    payout: &Pubkey,
+    wsol_mint: &Pubkey,
+    index: u64,
+) -> TransactionResult {
+    let edition_mint = get_edition_mint_address(vrgda_pda, index);
+
+    let ix_accounts = vrgda_exp::accounts::BuyEdition {
+        buyer: buyer.pubkey(),
+        recipient: buyer.pubkey(),
+        vrgda: *vrgda_pda,
+        wsol_mint: *wsol_mint,
+        buyer_wsol_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &buyer.pubkey(),
+            wsol_mint,
+            &spl_token_2022::ID,
+        ),
+        vrgda_sol_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            payout,
+            wsol_mint,
+            &spl_token_2022::ID,
+        ),
+        proceeds_vault: get_auction_proceeds_vault(svm, vrgda_pda, wsol_mint),
+        edition_mint,
+        recipient_edition_ata:
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &buyer.pubkey(),
+                &edition_mint,
+                &spl_token::ID,
+            ),
+        edition_metadata: get_metadata_pda(&edition_mint, &mpl_token_metadata::ID).0,
+        master_edition: get_master_edition_pda(&edition_mint),
+        collection_mint: None,
+        collection_metadata: None,
+        collection_master_edition: None,
+        collection_authority_record: None,
+        payout: *payout,
+        metadata_program: mpl_token_metadata::ID,
+        token_program: spl_token_2022::ID,
+        edition_token_program: spl_token::ID,
+        associated_token_program: spl_associated_token_account::ID,
+        system_program: system_program::ID,
+        rent: solana_sdk::sysvar::rent::ID,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::BuyEdition {}.data(),
+    };
+
+    let compute_budget_ix: Instruction =
+        ComputeBudgetInstruction::set_compute_unit_limit(BUY_COMPUTE_UNITS);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[compute_budget_ix, instruction],
+        Some(&buyer.pubkey()),
+        &[buyer],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn update_metadata(
+    svm: &mut litesvm::LiteSVM,
+    authority: &Keypair,
+    vrgda_pda: &Pubkey,
+    mint: &Pubkey,
+    metadata: Option<Pubkey>,
+    name: Option<&str>,
+    symbol: Option<&str>,
+    uri: Option<&str>,
+    lock: bool,
+) -> TransactionResult {
+    let ix_accounts = vrgda_exp::accounts::UpdateMetadata {
+        authority: authority.pubkey(),
+        vrgda: *vrgda_pda,
+        mint: *mint,
+        metadata,
+        metadata_program: metadata.map(|_| mpl_token_metadata::ID),
+        token_program: spl_token_2022::ID,
+        system_program: system_program::ID,
+    };
+
+    let ix = vrgda_exp::instruction::UpdateMetadata {
+        name: name.map(str::to_string),
+        symbol: symbol.map(str::to_string),
+        uri: uri.map(str::to_string),
+        lock,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: ix.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&authority.pubkey()),
+        &[authority],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn distribute_proceeds(
+    svm: &mut litesvm::LiteSVM,
+    cranker: &Keypair,
+    vrgda_pda: &Pubkey,
+    wsol_mint: &Pubkey,
+    payee_atas: &[Pubkey],
+) -> TransactionResult {
+    let ix_accounts = vrgda_exp::accounts::Distribute {
+        cranker: cranker.pubkey(),
+        vrgda: *vrgda_pda,
+        wsol_mint: *wsol_mint,
+        proceeds_vault: get_proceeds_vault_address(vrgda_pda, wsol_mint),
+        token_program: spl_token_2022::ID,
+    };
+
+    let mut accounts = ix_accounts.to_account_metas(None);
+    accounts.extend(
+        payee_atas
+            .iter()
+            .map(|ata| solana_sdk::instruction::AccountMeta::new(*ata, false)),
+    );
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts,
+        data: vrgda_exp::instruction::Distribute {}.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&cranker.pubkey()),
+        &[cranker],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn close_auction(
+    svm: &mut litesvm::LiteSVM,
+    authority: &Keypair,
+    vrgda_pda: &Pubkey,
+    mint: &Pubkey,
+    wsol_mint: &Pubkey,
+) -> TransactionResult {
+    let ata = |owner: &Pubkey, mint: &Pubkey| {
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            owner,
+            mint,
+            &spl_token_2022::ID,
+        )
+    };
+    let ix_accounts = vrgda_exp::accounts::CloseAuction {
+        authority: authority.pubkey(),
+        vrgda: *vrgda_pda,
+        vrgda_vault: ata(vrgda_pda, mint),
+        authority_ata: ata(&authority.pubkey(), mint),
+        proceeds_vault: get_auction_proceeds_vault(svm, vrgda_pda, wsol_mint),
+        mint: *mint,
+        wsol_mint: *wsol_mint,
+        token_program: spl_token_2022::ID,
+        associated_token_program: spl_associated_token_account::ID,
+        system_program: system_program::ID,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::CloseAuction {}.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&authority.pubkey()),
+        &[authority],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn refund(
+    svm: &mut litesvm::LiteSVM,
+    buyer: &Keypair,
+    vrgda_pda: &Pubkey,
+    mint: &Pubkey,
+    wsol_mint: &Pubkey,
+    vesting: bool,
+) -> TransactionResult {
+    let vesting_escrow = get_vesting_escrow_address(vrgda_pda, &buyer.pubkey());
+    let ix_accounts = vrgda_exp::accounts::Refund {
+        buyer: buyer.pubkey(),
+        vrgda: *vrgda_pda,
+        buyer_record: get_buyer_record_address(vrgda_pda, &buyer.pubkey()),
+        mint: *mint,
+        wsol_mint: *wsol_mint,
+        proceeds_vault: get_proceeds_vault_address(vrgda_pda, wsol_mint),
+        buyer_wsol_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &buyer.pubkey(),
+            wsol_mint,
+            &spl_token_2022::ID,
+        ),
+        buyer_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &buyer.pubkey(),
+            mint,
+            &spl_token_2022::ID,
+        ),
+        vesting_escrow: vesting.then_some(vesting_escrow),
+        vesting_vault: vesting.then(|| {
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &vesting_escrow,
+                mint,
+                &spl_token_2022::ID,
+            )
+        }),
+        token_program: spl_token_2022::ID,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::Refund {}.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&buyer.pubkey()),
+        &[buyer],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn claim_vested(
+    svm: &mut litesvm::LiteSVM,
+    beneficiary: &Keypair,
+    vrgda_pda: &Pubkey,
+    mint: &Pubkey,
+) -> TransactionResult {
+    let vesting_escrow = get_vesting_escrow_address(vrgda_pda, &beneficiary.pubkey());
+    let ix_accounts = vrgda_exp::accounts::ClaimVested {
+        beneficiary: beneficiary.pubkey(),
+        vesting_escrow,
+        vesting_vault: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &vesting_escrow,
+            mint,
+            &spl_token_2022::ID,
+        ),
+        beneficiary_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &beneficiary.pubkey(),
+            mint,
+            &spl_token_2022::ID,
+        ),
+        mint: *mint,
+        token_program: spl_token_2022::ID,
+        associated_token_program: spl_associated_token_account::ID,
+        system_program: system_program::ID,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::ClaimVested {}.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&beneficiary.pubkey()),
+        &[beneficiary],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn claim_reserve(
+    svm: &mut litesvm::LiteSVM,
+    recipient: &Keypair,
+    vrgda_pda: &Pubkey,
+    mint: &Pubkey,
+) -> TransactionResult {
+    let reserve_escrow = get_reserve_escrow_address(vrgda_pda);
+    let ix_accounts = vrgda_exp::accounts::ClaimReserve {
+        recipient: recipient.pubkey(),
+        reserve_escrow,
+        reserve_vault: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &reserve_escrow,
+            mint,
+            &spl_token_2022::ID,
+        ),
+        recipient_ata: spl_associated_token_account::get_associated_token_address_with_program_id(
+            &recipient.pubkey(),
+            mint,
+            &spl_token_2022::ID,
+        ),
+        mint: *mint,
+        token_program: spl_token_2022::ID,
+        associated_token_program: spl_associated_token_account::ID,
+        system_program: system_program::ID,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::ClaimReserve {}.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&recipient.pubkey()),
+        &[recipient],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn propose_authority(
+    svm: &mut litesvm::LiteSVM,
+    authority: &Keypair,
+    vrgda_pda: &Pubkey,
+    mint: &Pubkey,
+    metadata: Option<Pubkey>,
+    new_authority: &Pubkey,
+) -> TransactionResult {
+    let ix_accounts = vrgda_exp::accounts::ProposeAuthority {
+        authority: authority.pubkey(),
+        vrgda: *vrgda_pda,
+        mint: *mint,
+        metadata,
+        metadata_program: metadata.map(|_| mpl_token_metadata::ID),
+        token_program: spl_token_2022::ID,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::ProposeAuthority {
+            new_authority: *new_authority,
+        }
+        .data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&authority.pubkey()),
+        &[authority],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn accept_authority(
+    svm: &mut litesvm::LiteSVM,
+    new_authority: &Keypair,
+    vrgda_pda: &Pubkey,
+    mint: &Pubkey,
+    metadata: Option<Pubkey>,
+) -> TransactionResult {
+    let ix_accounts = vrgda_exp::accounts::AcceptAuthority {
+        new_authority: new_authority.pubkey(),
+        vrgda: *vrgda_pda,
+        mint: *mint,
+        metadata,
+        metadata_program: metadata.map(|_| mpl_token_metadata::ID),
+        token_program: spl_token_2022::ID,
+    };
+
+    let instruction = Instruction {
+        program_id: vrgda_exp::ID,
+        accounts: ix_accounts.to_account_metas(None),
+        data: vrgda_exp::instruction::AcceptAuthority {}.data(),
+    };
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&new_authority.pubkey()),
+        &[new_authority],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(transaction)
+}
+
+pub fn get_reserve_escrow_address(vrgda_pda: &Pubkey) -> Pubkey {
+    let seeds = [b"reserve", vrgda_pda.as_ref()];
+    let (address, _bump) = Pubkey::find_program_address(&seeds, &vrgda_exp::ID);
+    address
+}
+
+pub fn get_sale_mint_address(authority: &Pubkey, symbol: &str) -> Pubkey {
+    let seeds = [b"mint", authority.as_ref(), symbol.as_bytes()];
+    let (address, _bump) = Pubkey::find_program_address(&seeds, &vrgda_exp::ID);
+    address
+}
+
+pub fn get_edition_mint_address(vrgda_pda: &Pubkey, index: u64) -> Pubkey {
+    let seeds = [b"edition", vrgda_pda.as_ref(), &index.to_le_bytes()];
+    let (address, _bump) = Pubkey::find_program_address(&seeds, &vrgda_exp::ID);
+    address
+}
+
+pub fn get_master_edition_pda(mint: &Pubkey) -> Pubkey {
+    let seeds = [
+        b"metadata".as_ref(),
+        mpl_token_metadata::ID.as_ref(),
+        mint.as_ref(),
+        b"edition",
+    ];
+    let (address, _bump) = Pubkey::find_program_address(&seeds, &mpl_token_metadata::ID);
+    address
+}
+
+pub fn get_buyer_record_address(vrgda_pda: &Pubkey, buyer: &Pubkey) -> Pubkey {
+    let seeds = [b"buyer", vrgda_pda.as_ref(), buyer.as_ref()];
+    let (address, _bump) = Pubkey::find_program_address(&seeds, &vrgda_exp::ID);
+    address
+}
+
+pub fn get_caller_wallet_address(owner: &Pubkey) -> Pubkey {
+    let seeds = [b"wallet", owner.as_ref()];
+    let (address, _bump) = Pubkey::find_program_address(&seeds, &vrgda_caller::ID);
+    address
+}
+
+pub fn get_limit_order_address(vrgda_pda: &Pubkey, owner: &Pubkey, order_id: u64) -> Pubkey {
+    let seeds = [
+        b"limit_order".as_ref(),
+        vrgda_pda.as_ref(),
+        owner.as_ref(),
+        &order_id.to_le_bytes(),
+    ];
+    let (address, _bump) = Pubkey::find_program_address(&seeds, &vrgda_exp::ID);
+    address
+}
+
+pub fn get_dca_order_address(vrgda_pda: &Pubkey, owner: &Pubkey, order_id: u64) -> Pubkey {
+    let seeds = [
+        b"dca".as_ref(),
+        vrgda_pda.as_ref(),
+        owner.as_ref(),
+        &order_id.to_le_bytes(),
+    ];
+    let (address, _bump) = Pubkey::find_program_address(&seeds, &vrgda_exp::ID);
+    address
+}
+
+pub fn get_intent_nonce_address(vrgda_pda: &Pubkey, buyer: &Pubkey, nonce: u64) -> Pubkey {
+    let seeds = [
+        b"intent".as_ref(),
+        vrgda_pda.as_ref(),
+        buyer.as_ref(),
+        &nonce.to_le_bytes(),
+    ];
+    let (address, _bump) = Pubkey::find_program_address(&seeds, &vrgda_exp::ID);
+    address
+}
+
+pub fn get_purchase_commit_address(vrgda_pda: &Pubkey, buyer: &Pubkey) -> Pubkey {
+    let seeds = [b"commit", vrgda_pda.as_ref(), buyer.as_ref()];
+    let (address, _bump) = Pubkey::find_program_address(&seeds, &vrgda_exp::ID);
+    address
+}
+
+pub fn get_vesting_escrow_address(vrgda_pda: &Pubkey, beneficiary: &Pubkey) -> Pubkey {
+    let seeds = [b"vesting", vrgda_pda.as_ref(), beneficiary.as_ref()];
+    let (address, _bump) = Pubkey::find_program_address(&seeds, &vrgda_exp::ID);
+    address
+}
+
+/// The auction's proceeds vault, if it escrows its proceeds.
+pub fn get_auction_proceeds_vault(
+    svm: &mut litesvm::LiteSVM,
+    vrgda_pda: &Pubkey,
+    wsol_mint: &Pubkey,
+) -> Option<Pubkey> {
+    let vrgda: VRGDA = fetch_account_data(svm, vrgda_pda);
+    vrgda
+        .escrows_proceeds()
+        .then(|| get_proceeds_vault_address(vrgda_pda, wsol_mint))
+}
+
+pub fn get_proceeds_vault_address(vrgda_pda: &Pubkey, wsol_mint: &Pubkey) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address_with_program_id(
+        vrgda_pda,
+        wsol_mint,
+        &spl_token_2022::ID,
+    )
+}
+
+pub fn get_token_balance(svm: &litesvm::LiteSVM, token_account: &Pubkey) -> u64 {
+    let account = svm.get_account(token_account).unwrap();
+    Account::unpack(&account.data[..Account::LEN])
+        .unwrap()
+        .amount
+}
+
+pub fn get_mint_supply(svm: &litesvm::LiteSVM, mint: &Pubkey) -> u64 {
+    let account = svm.get_account(mint).unwrap();
+    spl_token_2022::state::Mint::unpack(&account.data[..spl_token_2022::state::Mint::LEN])
+        .unwrap()
+        .supply
+}
+
+/// Asserts that `result` failed with `error` from the VRGDA program.
+pub fn assert_vrgda_error(result: TransactionResult, error: vrgda_exp::error::VRGDAError) {
+    match result.unwrap_err().err {
+        solana_sdk::transaction::TransactionError::InstructionError(
+            _,
+            solana_sdk::instruction::InstructionError::Custom(code),
+        ) => assert_eq!(code, u32::from(error), "expected {:?}", error),
+        other => panic!("expected {:?}, got {:?}", error, other),
+    }
 }
 
 pub fn fetch_account_data<T: AccountDeserialize>(
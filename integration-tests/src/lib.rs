@@ -7,7 +7,7 @@ mod tests {
     use crate::helpers::get_metadata_pda;
 
     use super::*;
-    use anchor_lang::{pubkey, AccountDeserialize};
+    use anchor_lang::{pubkey, AccountDeserialize, AnchorDeserialize, Space};
     use anchor_spl::associated_token::spl_associated_token_account;
     use anchor_spl::token_2022::spl_token_2022;
     use litesvm::LiteSVM;
@@ -15,7 +15,14 @@ mod tests {
     use solana_sdk::pubkey::Pubkey;
     use solana_sdk::signer::Signer;
     use solana_sdk::{clock::Clock, signature::Keypair};
-    use vrgda_exp::state::{vrgda_price_for_amount_for_tests, VRGDA};
+    use vrgda_exp::error::VRGDAError;
+    use vrgda_exp::math::ONE;
+    use vrgda_exp::state::{
+        vrgda_price_for_amount_for_tests, AuctionParams, BatchPurchase, BuyerRecord, CommitRevealConfig, DcaOrder,
+        EditionConfig, MetadataMode, MintMode, Payee, PurchaseCommit, PurchaseIntent,
+        PurchaseReceipt, RateLimits, ReserveConfig, Schedule, SoftCap, VestingConfig, VestingEscrow,
+        VrgdaV0, VRGDA, VRGDA_RESERVED_BYTES, VRGDA_V0_SPACE, VRGDA_VERSION,
+    };
 
     pub const METAPLEX_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
 
@@ -75,7 +82,7 @@ mod tests {
                payer.pubkey(), mint.pubkey(), wsol_mint.pubkey(), authority.pubkey(), destination.pubkey());
 
         println!("program ID: {:?}", vrgda_exp::ID);
-        let target_price_wad = 4_000_000_000u128;
+        let target_price_wad = 4 * ONE;
         let buy_window = 60i64; // 60 seconds
 
         // Initialize the SVM
@@ -163,7 +170,7 @@ mod tests {
             &wsol_mint,
             &authority,
             metadata_pda,
-            4_000_000_000u128,
+            4 * ONE,
             50,
             0,
             1_000_000_000,
@@ -209,6 +216,2386 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_buy_without_payees_skips_proceeds_vault() {
+        let mut fixture = helpers::AuctionFixture::new();
+
+        fixture.initialize(helpers::default_auction_params());
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            buyer,
+            vrgda_pda,
+            vrgda_sol_ata,
+            vrgda_mint_ata,
+            ..
+        } = fixture;
+
+        helpers::buy_tokens(
+            &mut svm,
+            &payer,
+            &buyer,
+            &authority,
+            &vrgda_pda,
+            &vrgda_sol_ata,
+            &vrgda_mint_ata,
+            &mint,
+            &wsol_mint,
+            1_000_000,
+        );
+
+        // Without payees or a soft cap there is nothing to escrow.
+        let proceeds_vault = helpers::get_proceeds_vault_address(&vrgda_pda, &wsol_mint.pubkey());
+        assert!(svm.get_account(&proceeds_vault).is_none());
+    }
+
+    #[test]
+    fn test_distribute_splits_proceeds() {
+        let mut fixture = helpers::AuctionFixture::new();
+        let payee_wallets = [Keypair::new(), Keypair::new(), Keypair::new()];
+
+        // 3333 + 3333 + 3334 bps leaves rounding dust on most totals.
+        let payees = vec![
+            Payee {
+                wallet: payee_wallets[0].pubkey(),
+                share_bps: 3_333,
+            },
+            Payee {
+                wallet: payee_wallets[1].pubkey(),
+                share_bps: 3_333,
+            },
+            Payee {
+                wallet: payee_wallets[2].pubkey(),
+                share_bps: 3_334,
+            },
+        ];
+
+        fixture.initialize(AuctionParams {
+            payees: payees.clone(),
+            ..helpers::default_auction_params()
+        });
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            buyer,
+            vrgda_pda,
+            vrgda_mint_ata,
+            vrgda_sol_ata,
+            ..
+        } = fixture;
+
+        helpers::buy_tokens(
+            &mut svm,
+            &payer,
+            &buyer,
+            &authority,
+            &vrgda_pda,
+            &vrgda_sol_ata,
+            &vrgda_mint_ata,
+            &mint,
+            &wsol_mint,
+            1_000_000,
+        );
+
+        let proceeds_vault = helpers::get_proceeds_vault_address(&vrgda_pda, &wsol_mint.pubkey());
+        let collected = helpers::get_token_balance(&svm, &proceeds_vault);
+        assert!(collected > 0, "Split auctions should escrow the payment");
+        assert_eq!(helpers::get_token_balance(&svm, &vrgda_sol_ata), 0);
+
+        let payee_atas: Vec<Pubkey> = payee_wallets
+            .iter()
+            .map(|wallet| {
+                helpers::initialize_ata(&mut svm, &payer, wsol_mint.pubkey(), wallet.pubkey())
+                    .unwrap();
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &wallet.pubkey(),
+                    &wsol_mint.pubkey(),
+                    &spl_token_2022::ID,
+                )
+            })
+            .collect();
+
+        // The auction can't close while the proceeds wait in the vault.
+        helpers::assert_vrgda_error(
+            helpers::close_auction(
+                &mut svm,
+                &authority,
+                &vrgda_pda,
+                &mint.pubkey(),
+                &wsol_mint.pubkey(),
+            ),
+            VRGDAError::ProceedsNotDistributed,
+        );
+
+        // Anyone can crank the distribution.
+        let cranker = Keypair::new();
+        svm.airdrop(&cranker.pubkey(), 1_000_000_000).unwrap();
+        helpers::distribute_proceeds(
+            &mut svm,
+            &cranker,
+            &vrgda_pda,
+            &wsol_mint.pubkey(),
+            &payee_atas,
+        )
+        .unwrap();
+
+        let received: Vec<u64> = payee_atas
+            .iter()
+            .map(|ata| helpers::get_token_balance(&svm, ata))
+            .collect();
+        let expected: Vec<u64> = payees
+            .iter()
+            .map(|p| (collected as u128 * p.share_bps as u128 / 10_000) as u64)
+            .collect();
+        let dust = collected - expected.iter().sum::<u64>();
+
+        assert_eq!(received[0], expected[0] + dust);
+        assert_eq!(received[1], expected[1]);
+        assert_eq!(received[2], expected[2]);
+        assert_eq!(received.iter().sum::<u64>(), collected);
+        assert_eq!(helpers::get_token_balance(&svm, &proceeds_vault), 0);
+
+        // Once distributed, closing hands the unsold tokens back.
+        let unsold = helpers::get_token_balance(&svm, &vrgda_mint_ata);
+        svm.expire_blockhash();
+        helpers::close_auction(
+            &mut svm,
+            &authority,
+            &vrgda_pda,
+            &mint.pubkey(),
+            &wsol_mint.pubkey(),
+        )
+        .unwrap();
+        let vrgda_account = svm.get_account(&vrgda_pda).unwrap_or_default();
+        assert_eq!(vrgda_account.lamports, 0);
+        let authority_ata =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &authority.pubkey(),
+                &mint.pubkey(),
+                &spl_token_2022::ID,
+            );
+        assert_eq!(helpers::get_token_balance(&svm, &authority_ata), unsold);
+        assert_eq!(helpers::get_token_balance(&svm, &vrgda_mint_ata), 0);
+    }
+
+    #[test]
+    fn test_vesting_escrow_releases_linearly() {
+        let mut fixture = helpers::AuctionFixture::new();
+
+        let vesting = VestingConfig {
+            cliff_seconds: 100,
+            duration_seconds: 1_000,
+        };
+
+        fixture.initialize(AuctionParams {
+            vesting: Some(vesting),
+            ..helpers::default_auction_params()
+        });
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            buyer,
+            vrgda_pda,
+            vrgda_mint_ata,
+            vrgda_sol_ata,
+            ..
+        } = fixture;
+
+        let amount = 1_000_000;
+        helpers::buy_vested_tokens(
+            &mut svm,
+            &payer,
+            &buyer,
+            &authority,
+            &vrgda_pda,
+            &vrgda_sol_ata,
+            &vrgda_mint_ata,
+            &mint,
+            &wsol_mint,
+            amount,
+        )
+        .unwrap();
+
+        let buyer_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+            &buyer.pubkey(),
+            &mint.pubkey(),
+            &spl_token_2022::ID,
+        );
+        let escrow_address = helpers::get_vesting_escrow_address(&vrgda_pda, &buyer.pubkey());
+        let escrow: VestingEscrow = helpers::fetch_account_data(&mut svm, &escrow_address);
+        assert_eq!(escrow.total_amount, amount);
+        assert_eq!(escrow.beneficiary, buyer.pubkey());
+        assert_eq!(helpers::get_token_balance(&svm, &buyer_ata), 0);
+
+        // Nothing is claimable before the cliff.
+        assert!(helpers::claim_vested(&mut svm, &buyer, &vrgda_pda, &mint.pubkey()).is_err());
+
+        let mut clock = svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = escrow.start_timestamp + 500;
+        svm.set_sysvar::<Clock>(&clock);
+        svm.expire_blockhash();
+
+        helpers::claim_vested(&mut svm, &buyer, &vrgda_pda, &mint.pubkey()).unwrap();
+        assert_eq!(helpers::get_token_balance(&svm, &buyer_ata), amount / 2);
+
+        clock.unix_timestamp = escrow.start_timestamp + 1_000;
+        svm.set_sysvar::<Clock>(&clock);
+        svm.expire_blockhash();
+
+        helpers::claim_vested(&mut svm, &buyer, &vrgda_pda, &mint.pubkey()).unwrap();
+        assert_eq!(helpers::get_token_balance(&svm, &buyer_ata), amount);
+
+        let escrow: VestingEscrow = helpers::fetch_account_data(&mut svm, &escrow_address);
+        assert_eq!(escrow.claimed_amount, amount);
+    }
+
+    #[test]
+    fn test_vesting_top_up_does_not_inherit_start() {
+        let mut fixture = helpers::AuctionFixture::new();
+
+        let vesting = VestingConfig {
+            cliff_seconds: 100,
+            duration_seconds: 1_000,
+        };
+
+        fixture.initialize(AuctionParams {
+            vesting: Some(vesting),
+            ..helpers::default_auction_params()
+        });
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            buyer,
+            vrgda_pda,
+            vrgda_mint_ata,
+            vrgda_sol_ata,
+            ..
+        } = fixture;
+
+        let buy = |svm: &mut LiteSVM, amount: u64| {
+            helpers::buy_vested_tokens(
+                svm,
+                &payer,
+                &buyer,
+                &authority,
+                &vrgda_pda,
+                &vrgda_sol_ata,
+                &vrgda_mint_ata,
+                &mint,
+                &wsol_mint,
+                amount,
+            )
+        };
+        let escrow_address = helpers::get_vesting_escrow_address(&vrgda_pda, &buyer.pubkey());
+
+        // A tiny early buy that fully vests and is claimed.
+        buy(&mut svm, 1_000).unwrap();
+        let escrow: VestingEscrow = helpers::fetch_account_data(&mut svm, &escrow_address);
+        let mut clock = svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = escrow.start_timestamp + vesting.duration_seconds;
+        svm.set_sysvar::<Clock>(&clock);
+        svm.expire_blockhash();
+        helpers::claim_vested(&mut svm, &buyer, &vrgda_pda, &mint.pubkey()).unwrap();
+
+        // The large buy afterwards starts vesting from about now.
+        buy(&mut svm, 1_000_000).unwrap();
+        let escrow: VestingEscrow = helpers::fetch_account_data(&mut svm, &escrow_address);
+        assert_eq!(escrow.total_amount, 1_001_000);
+        assert_eq!(escrow.claimable(clock.unix_timestamp).unwrap(), 0);
+
+        svm.expire_blockhash();
+        helpers::assert_vrgda_error(
+            helpers::claim_vested(&mut svm, &buyer, &vrgda_pda, &mint.pubkey()),
+            VRGDAError::NothingToClaim,
+        );
+    }
+
+    #[test]
+    fn test_reserve_is_kept_off_the_curve() {
+        let mut fixture = helpers::AuctionFixture::new();
+        let treasury = Keypair::new();
+
+        let total_supply = 1_000_000_000;
+        let reserve = ReserveConfig {
+            amount: 250_000_000,
+            recipient: treasury.pubkey(),
+            unlock: VestingConfig {
+                cliff_seconds: 0,
+                duration_seconds: 1_000,
+            },
+        };
+
+        fixture.initialize(AuctionParams {
+            total_supply,
+            reserve: Some(reserve),
+            ..helpers::default_auction_params()
+        });
+        let helpers::AuctionFixture {
+            mut svm,
+            mint,
+            authority,
+            vrgda_pda,
+            vrgda_mint_ata,
+            ..
+        } = fixture;
+
+        let vrgda_state: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        assert_eq!(vrgda_state.total_supply, total_supply);
+        assert_eq!(vrgda_state.tokens_sold, 0);
+        assert_eq!(vrgda_state.reserve_amount, reserve.amount);
+        assert_eq!(
+            helpers::get_token_balance(&svm, &vrgda_mint_ata),
+            total_supply
+        );
+
+        let reserve_escrow = helpers::get_reserve_escrow_address(&vrgda_pda);
+        let reserve_vault =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &reserve_escrow,
+                &mint.pubkey(),
+                &spl_token_2022::ID,
+            );
+        assert_eq!(
+            helpers::get_token_balance(&svm, &reserve_vault),
+            reserve.amount
+        );
+
+        svm.airdrop(&treasury.pubkey(), 1_000_000_000).unwrap();
+        let escrow: VestingEscrow = helpers::fetch_account_data(&mut svm, &reserve_escrow);
+        let mut clock = svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = escrow.start_timestamp + 1_000;
+        svm.set_sysvar::<Clock>(&clock);
+
+        // Only the recipient can claim.
+        assert!(helpers::claim_reserve(&mut svm, &authority, &vrgda_pda, &mint.pubkey()).is_err());
+        helpers::claim_reserve(&mut svm, &treasury, &vrgda_pda, &mint.pubkey()).unwrap();
+
+        let treasury_ata =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &treasury.pubkey(),
+                &mint.pubkey(),
+                &spl_token_2022::ID,
+            );
+        assert_eq!(
+            helpers::get_token_balance(&svm, &treasury_ata),
+            reserve.amount
+        );
+        assert_eq!(helpers::get_token_balance(&svm, &reserve_vault), 0);
+    }
+
+    #[test]
+    fn test_refund_after_failed_soft_cap() {
+        let mut fixture = helpers::AuctionFixture::new();
+
+        let soft_cap = SoftCap {
+            amount: u64::MAX,
+            deadline: 100,
+        };
+
+        fixture.initialize(AuctionParams {
+            soft_cap: Some(soft_cap),
+            ..helpers::default_auction_params()
+        });
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            buyer,
+            vrgda_pda,
+            vrgda_mint_ata,
+            vrgda_sol_ata,
+            ..
+        } = fixture;
+
+        let amount = 1_000_000;
+        helpers::buy_tokens(
+            &mut svm,
+            &payer,
+            &buyer,
+            &authority,
+            &vrgda_pda,
+            &vrgda_sol_ata,
+            &vrgda_mint_ata,
+            &mint,
+            &wsol_mint,
+            amount,
+        );
+
+        let buyer_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+            &buyer.pubkey(),
+            &mint.pubkey(),
+            &spl_token_2022::ID,
+        );
+        let buyer_wsol_ata =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &buyer.pubkey(),
+                &wsol_mint.pubkey(),
+                &spl_token_2022::ID,
+            );
+        let proceeds_vault = helpers::get_proceeds_vault_address(&vrgda_pda, &wsol_mint.pubkey());
+        let buyer_record = helpers::get_buyer_record_address(&vrgda_pda, &buyer.pubkey());
+
+        let record: BuyerRecord = helpers::fetch_account_data(&mut svm, &buyer_record);
+        assert_eq!(record.tokens_bought, amount);
+        assert!(record.quote_spent > 0);
+        assert_eq!(helpers::get_token_balance(&svm, &buyer_ata), amount);
+        assert_eq!(helpers::get_token_balance(&svm, &vrgda_sol_ata), 0);
+        assert_eq!(
+            helpers::get_token_balance(&svm, &proceeds_vault),
+            record.quote_spent
+        );
+
+        // Refunds only open once the deadline has passed.
+        assert!(helpers::refund(
+            &mut svm,
+            &buyer,
+            &vrgda_pda,
+            &mint.pubkey(),
+            &wsol_mint.pubkey(),
+            false
+        )
+        .is_err());
+
+        let mut clock = svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = soft_cap.deadline + 1;
+        svm.set_sysvar::<Clock>(&clock);
+        svm.expire_blockhash();
+
+        // Escrowed proceeds stay locked while the cap is unmet.
+        assert!(helpers::distribute_proceeds(
+            &mut svm,
+            &authority,
+            &vrgda_pda,
+            &wsol_mint.pubkey(),
+            &[vrgda_sol_ata]
+        )
+        .is_err());
+        helpers::assert_vrgda_error(
+            helpers::close_auction(
+                &mut svm,
+                &authority,
+                &vrgda_pda,
+                &mint.pubkey(),
+                &wsol_mint.pubkey(),
+            ),
+            VRGDAError::RefundsOutstanding,
+        );
+
+        let wsol_before = helpers::get_token_balance(&svm, &buyer_wsol_ata);
+        helpers::refund(
+            &mut svm,
+            &buyer,
+            &vrgda_pda,
+            &mint.pubkey(),
+            &wsol_mint.pubkey(),
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(
+            helpers::get_token_balance(&svm, &buyer_wsol_ata),
+            wsol_before + record.quote_spent
+        );
+        assert_eq!(helpers::get_token_balance(&svm, &buyer_ata), 0);
+        assert_eq!(helpers::get_token_balance(&svm, &proceeds_vault), 0);
+        assert!(svm
+            .get_account(&buyer_record)
+            .is_none_or(|account| account.data.is_empty()));
+
+        let vrgda_state: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        assert_eq!(vrgda_state.total_raised, 0);
+
+        // With every buyer paid back the auction can close.
+        helpers::close_auction(
+            &mut svm,
+            &authority,
+            &vrgda_pda,
+            &mint.pubkey(),
+            &wsol_mint.pubkey(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_perpetual_mode_mints_on_buy() {
+        let mut fixture = helpers::AuctionFixture::new();
+
+        // No supply is set aside, emission follows the schedule forever.
+        fixture.initialize(AuctionParams {
+            total_supply: 0,
+            mint_mode: MintMode::Perpetual,
+            ..helpers::default_auction_params()
+        });
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            buyer,
+            vrgda_pda,
+            vrgda_mint_ata,
+            vrgda_sol_ata,
+            ..
+        } = fixture;
+
+        assert_eq!(helpers::get_mint_supply(&svm, &mint.pubkey()), 0);
+        assert_eq!(helpers::get_token_balance(&svm, &vrgda_mint_ata), 0);
+
+        let amount = 1_000_000;
+        helpers::buy_tokens(
+            &mut svm,
+            &payer,
+            &buyer,
+            &authority,
+            &vrgda_pda,
+            &vrgda_sol_ata,
+            &vrgda_mint_ata,
+            &mint,
+            &wsol_mint,
+            amount,
+        );
+
+        let buyer_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+            &buyer.pubkey(),
+            &mint.pubkey(),
+            &spl_token_2022::ID,
+        );
+        assert_eq!(helpers::get_token_balance(&svm, &buyer_ata), amount);
+        assert_eq!(helpers::get_mint_supply(&svm, &mint.pubkey()), amount);
+        assert_eq!(helpers::get_token_balance(&svm, &vrgda_mint_ata), 0);
+
+        let vrgda_state: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        assert_eq!(vrgda_state.tokens_sold, amount);
+        assert_eq!(vrgda_state.total_supply, 0);
+    }
+
+    #[test]
+    fn test_transfer_fee_mint_delivers_full_amount() {
+        let mut fixture = helpers::AuctionFixture::new();
+
+        fixture
+            .svm
+            .airdrop(&fixture.payer.pubkey(), 100_000_000_000)
+            .unwrap();
+        // 1% fee on every transfer of the sale token.
+        helpers::init_mint_account_with_transfer_fee(
+            &mut fixture.svm,
+            &fixture.payer,
+            &fixture.mint,
+            &fixture.authority.pubkey(),
+            100,
+            u64::MAX,
+        );
+
+        let total_supply = 1_000_000_000;
+        fixture.initialize(AuctionParams {
+            total_supply,
+            ..helpers::default_auction_params()
+        });
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            buyer,
+            vrgda_pda,
+            vrgda_mint_ata,
+            vrgda_sol_ata,
+            ..
+        } = fixture;
+
+        let amount = 1_000_000;
+        helpers::buy_tokens(
+            &mut svm,
+            &payer,
+            &buyer,
+            &authority,
+            &vrgda_pda,
+            &vrgda_sol_ata,
+            &vrgda_mint_ata,
+            &mint,
+            &wsol_mint,
+            amount,
+        );
+
+        let buyer_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+            &buyer.pubkey(),
+            &mint.pubkey(),
+            &spl_token_2022::ID,
+        );
+        assert_eq!(helpers::get_token_balance(&svm, &buyer_ata), amount);
+
+        // The vault paid the withheld fee on top of the purchase.
+        let vault_balance = helpers::get_token_balance(&svm, &vrgda_mint_ata);
+        let sent = total_supply - vault_balance;
+        assert!(sent > amount);
+
+        let vrgda_state: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        assert_eq!(vrgda_state.tokens_sold, amount);
+        assert_eq!(vrgda_state.total_supply, vault_balance);
+    }
+
+    #[test]
+    fn test_token_2022_metadata_without_metaplex() {
+        use anchor_spl::token_2022::spl_token_2022::{
+            extension::{BaseStateWithExtensions, StateWithExtensions},
+            state::Mint,
+        };
+        use anchor_spl::token_interface::spl_token_metadata_interface::state::TokenMetadata;
+
+        let mut fixture = helpers::AuctionFixture::new();
+
+        fixture
+            .svm
+            .airdrop(&fixture.payer.pubkey(), 100_000_000_000)
+            .unwrap();
+        helpers::init_mint_account_with_metadata_pointer(
+            &mut fixture.svm,
+            &fixture.payer,
+            &fixture.mint,
+            &fixture.authority.pubkey(),
+        );
+
+        fixture.initialize(AuctionParams {
+            metadata_mode: MetadataMode::Token2022,
+            ..helpers::default_auction_params()
+        });
+        let helpers::AuctionFixture {
+            mut svm,
+            mint,
+            authority,
+            vrgda_pda,
+            ..
+        } = fixture;
+
+        let mint_account = svm.get_account(&mint.pubkey()).unwrap();
+        let mint_state = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+        let metadata = mint_state
+            .get_variable_len_extension::<TokenMetadata>()
+            .unwrap();
+        assert_eq!(metadata.name, "vrgda token");
+        assert_eq!(metadata.symbol, "VRGDA");
+        assert_eq!(metadata.uri, "https://example.com/metadata.json");
+        assert_eq!(
+            Option::<Pubkey>::from(metadata.update_authority),
+            Some(authority.pubkey())
+        );
+
+        let vrgda_state: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        assert_eq!(vrgda_state.metadata_mode, MetadataMode::Token2022);
+    }
+
+    #[test]
+    fn test_update_metadata_then_lock() {
+        use anchor_spl::token_2022::spl_token_2022::{
+            extension::{BaseStateWithExtensions, StateWithExtensions},
+            state::Mint,
+        };
+        use anchor_spl::token_interface::spl_token_metadata_interface::state::TokenMetadata;
+
+        let mut fixture = helpers::AuctionFixture::new();
+
+        fixture
+            .svm
+            .airdrop(&fixture.payer.pubkey(), 100_000_000_000)
+            .unwrap();
+        helpers::init_mint_account_with_metadata_pointer(
+            &mut fixture.svm,
+            &fixture.payer,
+            &fixture.mint,
+            &fixture.authority.pubkey(),
+        );
+
+        fixture.initialize(AuctionParams {
+            metadata_mode: MetadataMode::Token2022,
+            ..helpers::default_auction_params()
+        });
+        let helpers::AuctionFixture {
+            mut svm,
+            mint,
+            authority,
+            vrgda_pda,
+            ..
+        } = fixture;
+
+        // Only the auction authority can change the metadata.
+        let stranger = Keypair::new();
+        svm.airdrop(&stranger.pubkey(), 1_000_000_000).unwrap();
+        assert!(helpers::update_metadata(
+            &mut svm,
+            &stranger,
+            &vrgda_pda,
+            &mint.pubkey(),
+            None,
+            Some("stolen"),
+            None,
+            None,
+            false
+        )
+        .is_err());
+
+        helpers::update_metadata(
+            &mut svm,
+            &authority,
+            &vrgda_pda,
+            &mint.pubkey(),
+            None,
+            Some("a much longer vrgda token name"),
+            None,
+            Some("https://example.com/v2.json"),
+            true,
+        )
+        .unwrap();
+
+        let mint_account = svm.get_account(&mint.pubkey()).unwrap();
+        let mint_state = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+        let metadata = mint_state
+            .get_variable_len_extension::<TokenMetadata>()
+            .unwrap();
+        assert_eq!(metadata.name, "a much longer vrgda token name");
+        assert_eq!(metadata.symbol, "VRGDA");
+        assert_eq!(metadata.uri, "https://example.com/v2.json");
+        assert_eq!(Option::<Pubkey>::from(metadata.update_authority), None);
+
+        let vrgda_state: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        assert!(vrgda_state.metadata_locked);
+
+        svm.expire_blockhash();
+        assert!(helpers::update_metadata(
+            &mut svm,
+            &authority,
+            &vrgda_pda,
+            &mint.pubkey(),
+            None,
+            None,
+            Some("NEW"),
+            None,
+            false
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_initialize_creates_sale_mint() {
+        use anchor_spl::token_2022::spl_token_2022::{extension::StateWithExtensions, state::Mint};
+
+        let mut svm = LiteSVM::new();
+
+        svm.add_program_from_file(vrgda_exp::ID, "../target/deploy/vrgda_exp.so")
+            .expect("Failed to load VRGDA program");
+
+        let payer = Keypair::new();
+        let wsol_mint = Keypair::new();
+        let authority = Keypair::new();
+
+        svm.airdrop(&payer.pubkey(), 100_000_000_000).unwrap();
+        svm.airdrop(&authority.pubkey(), 100_000_000_000).unwrap();
+        helpers::init_mint_account(&mut svm, &payer, &wsol_mint);
+        let _ = helpers::initialize_ata(&mut svm, &payer, wsol_mint.pubkey(), authority.pubkey());
+
+        let total_supply = 1_000_000_000;
+        helpers::initialize_vrgda_with_mint(
+            &mut svm,
+            &authority,
+            &wsol_mint.pubkey(),
+            4 * ONE,
+            total_supply,
+            "vrgda token",
+            "VRGDA",
+            "https://example.com/metadata.json",
+            9,
+            MetadataMode::Token2022,
+            0,
+        )
+        .unwrap();
+
+        let mint = helpers::get_sale_mint_address(&authority.pubkey(), "VRGDA");
+        let vrgda_pda = helpers::get_vrgda_address(vrgda_exp::ID, &mint, &authority.pubkey());
+        let mint_account = svm.get_account(&mint).unwrap();
+        let mint_state = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+        assert_eq!(mint_state.base.decimals, 9);
+        assert_eq!(mint_state.base.supply, total_supply);
+        assert_eq!(
+            Option::<Pubkey>::from(mint_state.base.mint_authority),
+            Some(vrgda_pda)
+        );
+
+        let vrgda_vault =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &vrgda_pda,
+                &mint,
+                &spl_token_2022::ID,
+            );
+        assert_eq!(helpers::get_token_balance(&svm, &vrgda_vault), total_supply);
+    }
+
+    #[test]
+    fn test_edition_drop_mints_numbered_nfts() {
+        use mpl_token_metadata::accounts::Metadata;
+
+        let mut svm = LiteSVM::new();
+
+        svm.add_program_from_file(vrgda_exp::ID, "../target/deploy/vrgda_exp.so")
+            .expect("Failed to load VRGDA program");
+        svm.add_program_from_file(
+            METAPLEX_METADATA_PROGRAM_ID,
+            "../mpl/metaplex_token_metadata_program.so",
+        )
+        .expect("Failed to load Metaplex program");
+
+        let payer = Keypair::new();
+        let drop_mint = Keypair::new();
+        let wsol_mint = Keypair::new();
+        let authority = Keypair::new();
+        let buyer = Keypair::new();
+
+        svm.airdrop(&payer.pubkey(), 100_000_000_000).unwrap();
+        svm.airdrop(&authority.pubkey(), 100_000_000_000).unwrap();
+        svm.airdrop(&buyer.pubkey(), 100_000_000_000_000).unwrap();
+        helpers::init_mint_account(&mut svm, &payer, &drop_mint);
+        helpers::init_mint_account(&mut svm, &payer, &wsol_mint);
+        let _ = helpers::initialize_ata(&mut svm, &payer, wsol_mint.pubkey(), authority.pubkey());
+        let _ = helpers::initialize_ata(&mut svm, &buyer, wsol_mint.pubkey(), buyer.pubkey());
+        let buyer_wsol_ata =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &buyer.pubkey(),
+                &wsol_mint.pubkey(),
+                &spl_token_2022::ID,
+            );
+        let _ = helpers::mint_tokens(
+            &mut svm,
+            &payer,
+            &wsol_mint,
+            buyer_wsol_ata,
+            10_000_000_000_000_000,
+        );
+
+        let edition = EditionConfig {
+            name: "vrgda drop".to_string(),
+            symbol: "DROP".to_string(),
+            uri: "https://example.com/drop/".to_string(),
+            collection: None,
+        };
+
+        // The numbered names of the last edition have to fit Metaplex's limits.
+        assert!(helpers::initialize_edition_drop(
+            &mut svm,
+            &authority,
+            &drop_mint.pubkey(),
+            &wsol_mint.pubkey(),
+            4 * ONE,
+            0,
+            edition.clone(),
+            0,
+        )
+        .is_err());
+
+        helpers::initialize_edition_drop(
+            &mut svm,
+            &authority,
+            &drop_mint.pubkey(),
+            &wsol_mint.pubkey(),
+            4 * ONE,
+            10,
+            edition,
+            0,
+        )
+        .unwrap();
+
+        let vrgda_pda =
+            helpers::get_vrgda_address(vrgda_exp::ID, &drop_mint.pubkey(), &authority.pubkey());
+        for index in 0..2 {
+            helpers::buy_edition(
+                &mut svm,
+                &buyer,
+                &vrgda_pda,
+                &authority.pubkey(),
+                &wsol_mint.pubkey(),
+                index,
+            )
+            .unwrap();
+        }
+
+        for (index, number) in [(0u64, 1u64), (1, 2)] {
+            let edition_mint = helpers::get_edition_mint_address(&vrgda_pda, index);
+            let buyer_edition_ata =
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &buyer.pubkey(),
+                    &edition_mint,
+                    &anchor_spl::token::ID,
+                );
+            assert_eq!(helpers::get_token_balance(&svm, &buyer_edition_ata), 1);
+
+            let metadata_pda = get_metadata_pda(&edition_mint, &METAPLEX_METADATA_PROGRAM_ID).0;
+            let metadata =
+                Metadata::safe_deserialize(&svm.get_account(&metadata_pda).unwrap().data).unwrap();
+            assert_eq!(
+                metadata.name.trim_end_matches('\0'),
+                format!("vrgda drop #{}", number)
+            );
+            assert_eq!(
+                metadata.uri.trim_end_matches('\0'),
+                format!("https://example.com/drop/{}.json", number)
+            );
+        }
+
+        // Editions are booked like any other purchase, so commit-reveal closes
+        // `buy_edition` too.
+        let config = CommitRevealConfig {
+            min_delay_slots: 5,
+            reveal_timeout_slots: 20,
+        };
+        helpers::set_commit_reveal(&mut svm, &authority, &vrgda_pda, Some(config)).unwrap();
+        helpers::assert_vrgda_error(
+            helpers::buy_edition(
+                &mut svm,
+                &buyer,
+                &vrgda_pda,
+                &authority.pubkey(),
+                &wsol_mint.pubkey(),
+                2,
+            ),
+            VRGDAError::CommitRevealRequired,
+        );
+
+        let vrgda_state: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        assert_eq!(vrgda_state.mint_mode, MintMode::Editions);
+        assert_eq!(vrgda_state.tokens_sold, 2);
+        assert_eq!(vrgda_state.total_supply, 8);
+        assert!(vrgda_state.total_raised > 0);
+    }
+
+    #[test]
+    fn test_second_auction_of_same_mint() {
+        use solana_sdk::transaction::Transaction;
+
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            metadata: metadata_pda,
+            vrgda_sol_ata,
+            ..
+        } = helpers::AuctionFixture::new();
+
+        svm.airdrop(&payer.pubkey(), 100_000_000_000).unwrap();
+        svm.airdrop(&authority.pubkey(), 100_000_000_000).unwrap();
+        helpers::init_mint_account_with_authority(&mut svm, &payer, &mint, &authority.pubkey());
+
+        // The authority keeps some tokens to fund the second auction with.
+        let authority_ata =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &authority.pubkey(),
+                &mint.pubkey(),
+                &spl_token_2022::ID,
+            );
+        helpers::initialize_ata(&mut svm, &payer, mint.pubkey(), authority.pubkey()).unwrap();
+        let mint_to_ix = spl_token_2022::instruction::mint_to(
+            &spl_token_2022::ID,
+            &mint.pubkey(),
+            &authority_ata,
+            &authority.pubkey(),
+            &[],
+            500_000_000,
+        )
+        .unwrap();
+        svm.send_transaction(Transaction::new_signed_with_payer(
+            &[mint_to_ix],
+            Some(&authority.pubkey()),
+            &[&authority],
+            svm.latest_blockhash(),
+        ))
+        .unwrap();
+
+        let mut auctions = Vec::new();
+        for (auction_id, total_supply) in [(0u64, 1_000_000_000u64), (1, 500_000_000)] {
+            let vrgda_pda = helpers::get_auction_address(
+                vrgda_exp::ID,
+                &mint.pubkey(),
+                &authority.pubkey(),
+                auction_id,
+            );
+            let vrgda_mint_ata =
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &vrgda_pda,
+                    &mint.pubkey(),
+                    &spl_token_2022::ID,
+                );
+
+            helpers::initialize_auction(
+                &mut svm,
+                &vrgda_pda,
+                &vrgda_sol_ata,
+                &vrgda_mint_ata,
+                &payer,
+                &mint,
+                &wsol_mint,
+                &authority,
+                metadata_pda,
+                AuctionParams {
+                    auction_id,
+                    total_supply,
+                    ..helpers::default_auction_params()
+                },
+            );
+            assert_eq!(
+                helpers::get_token_balance(&svm, &vrgda_mint_ata),
+                total_supply
+            );
+            auctions.push((vrgda_pda, vrgda_mint_ata));
+        }
+
+        // The second auction was funded from the authority, not minted.
+        assert_eq!(helpers::get_token_balance(&svm, &authority_ata), 0);
+        assert_eq!(
+            helpers::get_mint_supply(&svm, &mint.pubkey()),
+            1_500_000_000
+        );
+
+        for (vrgda_pda, vrgda_mint_ata) in &auctions {
+            let buyer = Keypair::new();
+            helpers::buy_tokens(
+                &mut svm,
+                &payer,
+                &buyer,
+                &authority,
+                vrgda_pda,
+                &vrgda_sol_ata,
+                vrgda_mint_ata,
+                &mint,
+                &wsol_mint,
+                1_000_000,
+            );
+        }
+
+        for (auction_id, (vrgda_pda, _)) in auctions.iter().enumerate() {
+            let vrgda_state: VRGDA = helpers::fetch_account_data(&mut svm, vrgda_pda);
+            assert_eq!(vrgda_state.auction_id, auction_id as u64);
+            assert_eq!(vrgda_state.tokens_sold, 1_000_000);
+        }
+    }
+
+    #[test]
+    fn test_two_step_authority_transfer() {
+        let mut fixture = helpers::AuctionFixture::new();
+        let new_authority = Keypair::new();
+        let stranger = Keypair::new();
+
+        fixture.initialize(helpers::default_auction_params());
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            buyer,
+            metadata: metadata_pda,
+            vrgda_pda,
+            vrgda_mint_ata,
+            vrgda_sol_ata,
+        } = fixture;
+
+        svm.airdrop(&new_authority.pubkey(), 1_000_000_000).unwrap();
+        svm.airdrop(&stranger.pubkey(), 1_000_000_000).unwrap();
+
+        // Only the current authority can propose, only the proposed key can accept.
+        assert!(helpers::propose_authority(
+            &mut svm,
+            &stranger,
+            &vrgda_pda,
+            &mint.pubkey(),
+            Some(metadata_pda),
+            &stranger.pubkey(),
+        )
+        .is_err());
+        helpers::propose_authority(
+            &mut svm,
+            &authority,
+            &vrgda_pda,
+            &mint.pubkey(),
+            Some(metadata_pda),
+            &new_authority.pubkey(),
+        )
+        .unwrap();
+        assert!(helpers::accept_authority(
+            &mut svm,
+            &stranger,
+            &vrgda_pda,
+            &mint.pubkey(),
+            Some(metadata_pda),
+        )
+        .is_err());
+        helpers::accept_authority(
+            &mut svm,
+            &new_authority,
+            &vrgda_pda,
+            &mint.pubkey(),
+            Some(metadata_pda),
+        )
+        .unwrap();
+
+        let vrgda_state: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        assert_eq!(vrgda_state.authority, new_authority.pubkey());
+        assert_eq!(vrgda_state.creator, authority.pubkey());
+        assert_eq!(vrgda_state.pending_authority, None);
+
+        // The metadata update authority moved along with the auction.
+        let metadata = mpl_token_metadata::accounts::Metadata::safe_deserialize(
+            &svm.get_account(&metadata_pda).unwrap().data,
+        )
+        .unwrap();
+        assert_eq!(metadata.update_authority, new_authority.pubkey());
+        helpers::update_metadata(
+            &mut svm,
+            &new_authority,
+            &vrgda_pda,
+            &mint.pubkey(),
+            Some(metadata_pda),
+            None,
+            None,
+            Some("https://example.com/new.json"),
+            false,
+        )
+        .unwrap();
+
+        // The PDA keeps its address, and payments keep going to the old payout
+        // account until the new authority moves them.
+        assert_eq!(vrgda_state.payout, authority.pubkey());
+        helpers::buy_tokens(
+            &mut svm,
+            &payer,
+            &buyer,
+            &authority,
+            &vrgda_pda,
+            &vrgda_sol_ata,
+            &vrgda_mint_ata,
+            &mint,
+            &wsol_mint,
+            1_000_000,
+        );
+        let old_payout_balance = helpers::get_token_balance(&svm, &vrgda_sol_ata);
+        assert!(old_payout_balance > 0);
+
+        let new_sol_ata =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &new_authority.pubkey(),
+                &wsol_mint.pubkey(),
+                &spl_token_2022::ID,
+            );
+        helpers::initialize_ata(&mut svm, &payer, wsol_mint.pubkey(), new_authority.pubkey())
+            .unwrap();
+        assert!(
+            helpers::set_payout(&mut svm, &authority, &vrgda_pda, &authority.pubkey()).is_err()
+        );
+        helpers::set_payout(
+            &mut svm,
+            &new_authority,
+            &vrgda_pda,
+            &new_authority.pubkey(),
+        )
+        .unwrap();
+
+        // Buying against the old payout account fails the `has_one` check.
+        svm.expire_blockhash();
+        assert!(helpers::buy_tokens_for(
+            &mut svm,
+            &payer,
+            &buyer,
+            &buyer.pubkey(),
+            &authority,
+            &vrgda_pda,
+            &vrgda_sol_ata,
+            &vrgda_mint_ata,
+            &mint,
+            &wsol_mint,
+            1_000_000,
+            false,
+        )
+        .is_err());
+        helpers::buy_tokens(
+            &mut svm,
+            &payer,
+            &buyer,
+            &new_authority,
+            &vrgda_pda,
+            &new_sol_ata,
+            &vrgda_mint_ata,
+            &mint,
+            &wsol_mint,
+            1_000_000,
+        );
+        assert!(helpers::get_token_balance(&svm, &new_sol_ata) > 0);
+        assert_eq!(
+            helpers::get_token_balance(&svm, &vrgda_sol_ata),
+            old_payout_balance
+        );
+    }
+
+    #[test]
+    fn test_buy_for_recipient() {
+        let mut fixture = helpers::AuctionFixture::new();
+        let backend = Keypair::new();
+        let recipient = Keypair::new();
+
+        fixture.initialize(helpers::default_auction_params());
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            vrgda_pda,
+            vrgda_mint_ata,
+            vrgda_sol_ata,
+            ..
+        } = fixture;
+
+        helpers::buy_tokens_for(
+            &mut svm,
+            &payer,
+            &backend,
+            &recipient.pubkey(),
+            &authority,
+            &vrgda_pda,
+            &vrgda_sol_ata,
+            &vrgda_mint_ata,
+            &mint,
+            &wsol_mint,
+            1_000_000,
+            false,
+        )
+        .unwrap();
+
+        let recipient_ata =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &recipient.pubkey(),
+                &mint.pubkey(),
+                &spl_token_2022::ID,
+            );
+        let backend_ata =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &backend.pubkey(),
+                &mint.pubkey(),
+                &spl_token_2022::ID,
+            );
+        assert_eq!(helpers::get_token_balance(&svm, &recipient_ata), 1_000_000);
+        assert!(svm.get_account(&backend_ata).is_none());
+
+        // The purchase is recorded against the recipient, not the wallet that paid.
+        let record_address = helpers::get_buyer_record_address(&vrgda_pda, &recipient.pubkey());
+        let record: BuyerRecord = helpers::fetch_account_data(&mut svm, &record_address);
+        assert_eq!(record.buyer, recipient.pubkey());
+        assert_eq!(record.tokens_bought, 1_000_000);
+        assert!(svm
+            .get_account(&helpers::get_buyer_record_address(
+                &vrgda_pda,
+                &backend.pubkey()
+            ))
+            .is_none());
+    }
+
+    #[test]
+    fn test_buy_batch_for_several_recipients() {
+        let mut fixture = helpers::AuctionFixture::new();
+        let recipients: Vec<Pubkey> = (0..3).map(|_| Keypair::new().pubkey()).collect();
+
+        fixture.initialize(helpers::default_auction_params());
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            buyer,
+            vrgda_pda,
+            vrgda_sol_ata,
+            ..
+        } = fixture;
+
+        let vrgda_data: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        let now = svm.get_sysvar::<Clock>().unix_timestamp;
+        let expected_price = vrgda_data
+            .vrgda_price_for_amount(now, 0, 3_500_000)
+            .unwrap();
+
+        // The second recipient shows up twice and receives both amounts.
+        let purchase = |recipient_index, amount| BatchPurchase {
+            recipient_index,
+            amount,
+        };
+        let purchases = vec![
+            purchase(0, 1_000_000),
+            purchase(1, 500_000),
+            purchase(2, 1_500_000),
+            purchase(1, 500_000),
+        ];
+        helpers::buy_batch(
+            &mut svm,
+            &payer,
+            &buyer,
+            &authority,
+            &vrgda_pda,
+            &mint,
+            &wsol_mint,
+            &recipients,
+            purchases,
+        )
+        .unwrap();
+
+        let balance_of = |svm: &LiteSVM, owner: &Pubkey| {
+            helpers::get_token_balance(
+                svm,
+                &spl_associated_token_account::get_associated_token_address_with_program_id(
+                    owner,
+                    &mint.pubkey(),
+                    &spl_token_2022::ID,
+                ),
+            )
+        };
+        assert_eq!(balance_of(&svm, &recipients[0]), 1_000_000);
+        assert_eq!(balance_of(&svm, &recipients[1]), 1_000_000);
+        assert_eq!(balance_of(&svm, &recipients[2]), 1_500_000);
+
+        // The whole batch is priced as a single purchase and paid in one transfer.
+        let vrgda_data: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        assert_eq!(vrgda_data.tokens_sold, 3_500_000);
+        assert_eq!(
+            vrgda_data.current_price,
+            vrgda_exp::math::to_actual_mint_amount(&expected_price).unwrap()
+        );
+        assert_eq!(
+            helpers::get_token_balance(&svm, &vrgda_sol_ata),
+            vrgda_data.current_price
+        );
+    }
+
+    #[test]
+    fn test_limit_order_execute_and_cancel() {
+        let mut fixture = helpers::AuctionFixture::new();
+        let owner = Keypair::new();
+        let cranker = Keypair::new();
+
+        fixture.initialize(helpers::default_auction_params());
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            vrgda_pda,
+            vrgda_sol_ata,
+            ..
+        } = fixture;
+
+        let ata = |owner: &Pubkey, mint: &Pubkey| {
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                owner,
+                mint,
+                &spl_token_2022::ID,
+            )
+        };
+        let owner_wsol_ata = ata(&owner.pubkey(), &wsol_mint.pubkey());
+        let vrgda_data: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        let now = svm.get_sysvar::<Clock>().unix_timestamp;
+        let price = vrgda_exp::math::to_actual_mint_amount(
+            &vrgda_data
+                .vrgda_price_for_amount(now, 0, 1_000_000)
+                .unwrap(),
+        )
+        .unwrap();
+        let tip = 1_000;
+
+        // An order below the current price stays open until its owner cancels it.
+        helpers::place_limit_order(
+            &mut svm,
+            &payer,
+            &owner,
+            &vrgda_pda,
+            &mint.pubkey(),
+            &wsol_mint,
+            0,
+            1_000_000,
+            price / 2,
+            tip,
+        )
+        .unwrap();
+        let balance_while_open = helpers::get_token_balance(&svm, &owner_wsol_ata);
+        assert!(helpers::execute_limit_order(
+            &mut svm,
+            &cranker,
+            &owner.pubkey(),
+            &authority.pubkey(),
+            &vrgda_pda,
+            &mint.pubkey(),
+            &wsol_mint.pubkey(),
+            0,
+        )
+        .is_err());
+
+        helpers::cancel_limit_order(&mut svm, &owner, &vrgda_pda, &wsol_mint.pubkey(), 0).unwrap();
+        let first_order = helpers::get_limit_order_address(&vrgda_pda, &owner.pubkey(), 0);
+        assert!(svm.get_account(&first_order).is_none());
+        assert_eq!(
+            helpers::get_token_balance(&svm, &owner_wsol_ata),
+            balance_while_open + price / 2 + tip
+        );
+
+        // An order above the price is filled by the crank, which earns the tip.
+        svm.expire_blockhash();
+        helpers::place_limit_order(
+            &mut svm,
+            &payer,
+            &owner,
+            &vrgda_pda,
+            &mint.pubkey(),
+            &wsol_mint,
+            1,
+            1_000_000,
+            price * 2,
+            tip,
+        )
+        .unwrap();
+        let balance_while_open = helpers::get_token_balance(&svm, &owner_wsol_ata);
+        helpers::execute_limit_order(
+            &mut svm,
+            &cranker,
+            &owner.pubkey(),
+            &authority.pubkey(),
+            &vrgda_pda,
+            &mint.pubkey(),
+            &wsol_mint.pubkey(),
+            1,
+        )
+        .unwrap();
+
+        let second_order = helpers::get_limit_order_address(&vrgda_pda, &owner.pubkey(), 1);
+        assert!(svm.get_account(&second_order).is_none());
+        assert_eq!(
+            helpers::get_token_balance(&svm, &ata(&owner.pubkey(), &mint.pubkey())),
+            1_000_000
+        );
+        assert_eq!(
+            helpers::get_token_balance(&svm, &ata(&cranker.pubkey(), &wsol_mint.pubkey())),
+            tip
+        );
+        assert_eq!(helpers::get_token_balance(&svm, &vrgda_sol_ata), price);
+        assert_eq!(
+            helpers::get_token_balance(&svm, &owner_wsol_ata),
+            balance_while_open + price * 2 - price
+        );
+    }
+
+    #[test]
+    fn test_limit_order_tip_covers_quote_fee() {
+        let mut fixture = helpers::AuctionFixture::new();
+        let owner = Keypair::new();
+        let cranker = Keypair::new();
+
+        fixture
+            .svm
+            .airdrop(&fixture.payer.pubkey(), 100_000_000_000)
+            .unwrap();
+        // 1% fee on every transfer of the quote token.
+        helpers::init_mint_account_with_transfer_fee(
+            &mut fixture.svm,
+            &fixture.payer,
+            &fixture.wsol_mint,
+            &fixture.wsol_mint.pubkey(),
+            100,
+            u64::MAX,
+        );
+
+        fixture.initialize(helpers::default_auction_params());
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            vrgda_pda,
+            ..
+        } = fixture;
+
+        let vrgda_data: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        let now = svm.get_sysvar::<Clock>().unix_timestamp;
+        let price = vrgda_exp::math::to_actual_mint_amount(
+            &vrgda_data
+                .vrgda_price_for_amount(now, 0, 1_000_000)
+                .unwrap(),
+        )
+        .unwrap();
+        let tip = 100_000;
+
+        helpers::place_limit_order(
+            &mut svm,
+            &payer,
+            &owner,
+            &vrgda_pda,
+            &mint.pubkey(),
+            &wsol_mint,
+            0,
+            1_000_000,
+            price * 2,
+            tip,
+        )
+        .unwrap();
+        helpers::execute_limit_order(
+            &mut svm,
+            &cranker,
+            &owner.pubkey(),
+            &authority.pubkey(),
+            &vrgda_pda,
+            &mint.pubkey(),
+            &wsol_mint.pubkey(),
+            0,
+        )
+        .unwrap();
+
+        // The order paid the fee on top, the cranker receives the whole tip.
+        let cranker_wsol_ata =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &cranker.pubkey(),
+                &wsol_mint.pubkey(),
+                &spl_token_2022::ID,
+            );
+        assert_eq!(helpers::get_token_balance(&svm, &cranker_wsol_ata), tip);
+    }
+
+    #[test]
+    fn test_dca_order_buys_one_slice_per_interval() {
+        let mut fixture = helpers::AuctionFixture::new();
+        let owner = Keypair::new();
+        let cranker = Keypair::new();
+
+        fixture.initialize(helpers::default_auction_params());
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            vrgda_pda,
+            vrgda_sol_ata,
+            ..
+        } = fixture;
+
+        let owner_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+            &owner.pubkey(),
+            &mint.pubkey(),
+            &spl_token_2022::ID,
+        );
+        let owner_wsol_ata =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &owner.pubkey(),
+                &wsol_mint.pubkey(),
+                &spl_token_2022::ID,
+            );
+        let dca_address = helpers::get_dca_order_address(&vrgda_pda, &owner.pubkey(), 0);
+        let max_price_per_slice = 100_000_000_000;
+        helpers::create_dca_order(
+            &mut svm,
+            &payer,
+            &owner,
+            &vrgda_pda,
+            &mint.pubkey(),
+            &wsol_mint,
+            0,
+            100_000,
+            max_price_per_slice,
+            3_600,
+            2,
+        )
+        .unwrap();
+        let balance_while_open = helpers::get_token_balance(&svm, &owner_wsol_ata);
+
+        let execute = |svm: &mut LiteSVM| {
+            helpers::execute_dca(
+                svm,
+                &cranker,
+                &owner.pubkey(),
+                &authority.pubkey(),
+                &vrgda_pda,
+                &mint.pubkey(),
+                &wsol_mint.pubkey(),
+                0,
+            )
+        };
+
+        // The first slice is due right away, the next one only after an interval.
+        execute(&mut svm).unwrap();
+        assert_eq!(helpers::get_token_balance(&svm, &owner_ata), 100_000);
+        svm.expire_blockhash();
+        assert!(execute(&mut svm).is_err());
+
+        let order: DcaOrder = helpers::fetch_account_data(&mut svm, &dca_address);
+        assert_eq!(order.slices_remaining, 1);
+        let mut clock = svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = order.next_slice_timestamp;
+        svm.set_sysvar::<Clock>(&clock);
+        svm.expire_blockhash();
+
+        // The last slice closes the order and refunds what the slices did not spend.
+        execute(&mut svm).unwrap();
+        assert_eq!(helpers::get_token_balance(&svm, &owner_ata), 200_000);
+        assert!(svm.get_account(&dca_address).is_none());
+        let spent = helpers::get_token_balance(&svm, &vrgda_sol_ata);
+        assert_eq!(
+            helpers::get_token_balance(&svm, &owner_wsol_ata),
+            balance_while_open + 2 * max_price_per_slice - spent
+        );
+    }
+
+    #[test]
+    fn test_buy_with_intent_relayed() {
+        let mut fixture = helpers::AuctionFixture::new();
+        let relayer = Keypair::new();
+        fixture
+            .svm
+            .airdrop(&relayer.pubkey(), 1_000_000_000)
+            .unwrap();
+
+        fixture.initialize(helpers::default_auction_params());
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            buyer,
+            vrgda_pda,
+            ..
+        } = fixture;
+
+        helpers::approve_intent_allowance(
+            &mut svm,
+            &payer,
+            &buyer,
+            &vrgda_pda,
+            &wsol_mint,
+            u64::MAX,
+        );
+
+        let now = svm.get_sysvar::<Clock>().unix_timestamp;
+        let intent = PurchaseIntent {
+            vrgda: vrgda_pda,
+            amount: 1_000_000,
+            max_cost: 100_000_000_000,
+            nonce: 7,
+            expiry: now + 600,
+        };
+        let submit = |svm: &mut LiteSVM, signer: &Keypair, intent: PurchaseIntent| {
+            helpers::buy_with_intent(
+                svm,
+                &relayer,
+                &buyer.pubkey(),
+                signer,
+                &authority.pubkey(),
+                &mint.pubkey(),
+                &wsol_mint.pubkey(),
+                intent,
+            )
+        };
+
+        // The relayer pays for everything, the buyer never holds SOL.
+        submit(&mut svm, &buyer, intent).unwrap();
+        let buyer_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+            &buyer.pubkey(),
+            &mint.pubkey(),
+            &spl_token_2022::ID,
+        );
+        assert_eq!(helpers::get_token_balance(&svm, &buyer_ata), 1_000_000);
+        assert!(svm.get_account(&buyer.pubkey()).is_none());
+
+        // A replayed nonce, someone else's signature and an expired intent are rejected.
+        svm.expire_blockhash();
+        assert!(submit(&mut svm, &buyer, intent).is_err());
+        let forged = PurchaseIntent { nonce: 8, ..intent };
+        assert!(submit(&mut svm, &relayer, forged).is_err());
+        let expired = PurchaseIntent {
+            nonce: 9,
+            expiry: now - 1,
+            ..intent
+        };
+        assert!(submit(&mut svm, &buyer, expired).is_err());
+        assert_eq!(helpers::get_token_balance(&svm, &buyer_ata), 1_000_000);
+    }
+
+    #[test]
+    fn test_rate_limits() {
+        let mut fixture = helpers::AuctionFixture::new();
+        let first_buyer = Keypair::new();
+        let second_buyer = Keypair::new();
+
+        fixture.initialize(helpers::default_auction_params());
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            vrgda_pda,
+            vrgda_mint_ata,
+            vrgda_sol_ata,
+            ..
+        } = fixture;
+
+        let rate_limits = RateLimits {
+            max_per_transaction: Some(2_000_000),
+            max_per_slot: Some(3_000_000),
+            wallet_cooldown_seconds: Some(30),
+        };
+        helpers::set_rate_limits(&mut svm, &authority, &vrgda_pda, rate_limits).unwrap();
+        let vrgda_data: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        assert_eq!(vrgda_data.rate_limits, rate_limits);
+
+        // Every purchase airdrops to the buyer again, which needs a fresh blockhash.
+        let buy = |svm: &mut LiteSVM, buyer: &Keypair, amount: u64| {
+            svm.expire_blockhash();
+            helpers::buy_tokens_for(
+                svm,
+                &payer,
+                buyer,
+                &buyer.pubkey(),
+                &authority,
+                &vrgda_pda,
+                &vrgda_sol_ata,
+                &vrgda_mint_ata,
+                &mint,
+                &wsol_mint,
+                amount,
+                false,
+            )
+        };
+        let next_slot = |svm: &mut LiteSVM, seconds: i64| {
+            let mut clock = svm.get_sysvar::<Clock>();
+            clock.slot += 1;
+            clock.unix_timestamp += seconds;
+            svm.set_sysvar::<Clock>(&clock);
+        };
+
+        // Over the per-transaction limit.
+        assert!(buy(&mut svm, &first_buyer, 3_000_000).is_err());
+        buy(&mut svm, &first_buyer, 2_000_000).unwrap();
+
+        // Fits the transaction limit, but not what is left of the slot.
+        assert!(buy(&mut svm, &second_buyer, 2_000_000).is_err());
+        next_slot(&mut svm, 1);
+        buy(&mut svm, &second_buyer, 2_000_000).unwrap();
+
+        // The first buyer is still cooling down until 30 seconds have passed.
+        next_slot(&mut svm, 1);
+        assert!(buy(&mut svm, &first_buyer, 1_000_000).is_err());
+        next_slot(&mut svm, 30);
+        buy(&mut svm, &first_buyer, 1_000_000).unwrap();
+
+        let record: BuyerRecord = helpers::fetch_account_data(
+            &mut svm,
+            &helpers::get_buyer_record_address(&vrgda_pda, &first_buyer.pubkey()),
+        );
+        assert_eq!(record.tokens_bought, 3_000_000);
+        assert_eq!(
+            record.last_purchase_timestamp,
+            svm.get_sysvar::<Clock>().unix_timestamp
+        );
+    }
+
+    #[test]
+    fn test_commit_reveal() {
+        let mut fixture = helpers::AuctionFixture::new();
+        let late_buyer = Keypair::new();
+
+        fixture.initialize(helpers::default_auction_params());
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            buyer,
+            vrgda_pda,
+            vrgda_mint_ata,
+            vrgda_sol_ata,
+            ..
+        } = fixture;
+
+        let config = CommitRevealConfig {
+            min_delay_slots: 5,
+            reveal_timeout_slots: 20,
+        };
+        helpers::set_commit_reveal(&mut svm, &authority, &vrgda_pda, Some(config)).unwrap();
+
+        // Buying straight away is closed while commit-reveal is on.
+        assert!(helpers::buy_tokens_for(
+            &mut svm,
+            &payer,
+            &buyer,
+            &buyer.pubkey(),
+            &authority,
+            &vrgda_pda,
+            &vrgda_sol_ata,
+            &vrgda_mint_ata,
+            &mint,
+            &wsol_mint,
+            1_000_000,
+            false,
+        )
+        .is_err());
+
+        let ata = |owner: &Pubkey, mint: &Pubkey| {
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                owner,
+                mint,
+                &spl_token_2022::ID,
+            )
+        };
+        let warp_slots = |svm: &mut LiteSVM, slots: u64| {
+            let mut clock = svm.get_sysvar::<Clock>();
+            clock.slot += slots;
+            svm.set_sysvar::<Clock>(&clock);
+        };
+        let vrgda_data: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        let now = svm.get_sysvar::<Clock>().unix_timestamp;
+        let max_cost = 2 * vrgda_exp::math::to_actual_mint_amount(
+            &vrgda_data
+                .vrgda_price_for_amount(now, 0, 1_000_000)
+                .unwrap(),
+        )
+        .unwrap();
+        let salt = [7u8; 32];
+
+        helpers::commit_purchase(
+            &mut svm,
+            &payer,
+            &buyer,
+            &vrgda_pda,
+            &wsol_mint,
+            PurchaseCommit::hash(1_000_000, max_cost, &salt),
+            max_cost,
+        )
+        .unwrap();
+        let commit_address = helpers::get_purchase_commit_address(&vrgda_pda, &buyer.pubkey());
+        let commit: PurchaseCommit = helpers::fetch_account_data(&mut svm, &commit_address);
+        let slot = svm.get_sysvar::<Clock>().slot;
+        assert_eq!(commit.reveal_slot, slot + 5);
+        assert_eq!(commit.expiry_slot, slot + 20);
+        assert_eq!(
+            helpers::get_token_balance(&svm, &ata(&commit_address, &wsol_mint.pubkey())),
+            max_cost
+        );
+
+        let buyer_wsol_ata = ata(&buyer.pubkey(), &wsol_mint.pubkey());
+        let balance_while_committed = helpers::get_token_balance(&svm, &buyer_wsol_ata);
+        let proceeds_before = helpers::get_token_balance(&svm, &vrgda_sol_ata);
+        let reveal = |svm: &mut LiteSVM, salt: [u8; 32]| {
+            svm.expire_blockhash();
+            helpers::reveal_purchase(
+                svm,
+                &buyer,
+                &authority.pubkey(),
+                &vrgda_pda,
+                &mint.pubkey(),
+                &wsol_mint.pubkey(),
+                1_000_000,
+                max_cost,
+                salt,
+            )
+        };
+
+        // Too early, then a salt that does not match the commitment.
+        assert!(reveal(&mut svm, salt).is_err());
+        warp_slots(&mut svm, 5);
+        assert!(reveal(&mut svm, [8u8; 32]).is_err());
+        reveal(&mut svm, salt).unwrap();
+
+        assert!(svm.get_account(&commit_address).is_none());
+        assert_eq!(
+            helpers::get_token_balance(&svm, &ata(&buyer.pubkey(), &mint.pubkey())),
+            1_000_000
+        );
+        // The buyer got back whatever the reveal price left of the deposit.
+        let payment =
+            balance_while_committed + max_cost - helpers::get_token_balance(&svm, &buyer_wsol_ata);
+        assert!(payment > 0 && payment <= max_cost);
+        assert_eq!(
+            helpers::get_token_balance(&svm, &vrgda_sol_ata),
+            proceeds_before + payment
+        );
+
+        // A commit left unrevealed past the timeout is reclaimed in full.
+        helpers::commit_purchase(
+            &mut svm,
+            &payer,
+            &late_buyer,
+            &vrgda_pda,
+            &wsol_mint,
+            PurchaseCommit::hash(1_000_000, max_cost, &salt),
+            max_cost,
+        )
+        .unwrap();
+        let late_wsol_ata = ata(&late_buyer.pubkey(), &wsol_mint.pubkey());
+        let late_balance = helpers::get_token_balance(&svm, &late_wsol_ata);
+        assert!(
+            helpers::reclaim_commit(&mut svm, &late_buyer, &vrgda_pda, &wsol_mint.pubkey())
+                .is_err()
+        );
+        warp_slots(&mut svm, 20);
+        svm.expire_blockhash();
+        helpers::reclaim_commit(&mut svm, &late_buyer, &vrgda_pda, &wsol_mint.pubkey()).unwrap();
+        assert_eq!(
+            helpers::get_token_balance(&svm, &late_wsol_ata),
+            late_balance + max_cost
+        );
+        assert!(svm
+            .get_account(&helpers::get_purchase_commit_address(
+                &vrgda_pda,
+                &late_buyer.pubkey()
+            ))
+            .is_none());
+    }
+
+    #[test]
+    fn test_migrate_v0_account() {
+        assert_eq!(VrgdaV0::INIT_SPACE, VRGDA_V0_SPACE);
+
+        let mut svm = LiteSVM::new();
+
+        svm.add_program_from_file(vrgda_exp::ID, "../target/deploy/vrgda_exp.so")
+            .expect("Failed to load VRGDA program");
+
+        let payer = Keypair::new();
+        let mint = Keypair::new();
+        let wsol_mint = Keypair::new();
+        let authority = Keypair::new();
+        let stranger = Keypair::new();
+        let buyer = Keypair::new();
+        svm.airdrop(&payer.pubkey(), 10_000_000_000).unwrap();
+        svm.airdrop(&authority.pubkey(), 1_000_000_000).unwrap();
+        svm.airdrop(&stranger.pubkey(), 1_000_000_000).unwrap();
+
+        // An auction as the first release left it: a pre-minted vault owned by
+        // the address derived without `auction_id`.
+        let (vrgda_pda, bump) =
+            helpers::get_legacy_vrgda_address(&mint.pubkey(), &authority.pubkey());
+        let vrgda_mint_ata =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &vrgda_pda,
+                &mint.pubkey(),
+                &spl_token_2022::ID,
+            );
+        let vrgda_sol_ata =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &authority.pubkey(),
+                &wsol_mint.pubkey(),
+                &spl_token_2022::ID,
+            );
+        helpers::init_mint_account(&mut svm, &payer, &mint);
+        helpers::init_mint_account(&mut svm, &payer, &wsol_mint);
+        helpers::initialize_ata(&mut svm, &payer, mint.pubkey(), vrgda_pda).unwrap();
+        helpers::initialize_ata(&mut svm, &payer, wsol_mint.pubkey(), authority.pubkey()).unwrap();
+        helpers::mint_tokens(&mut svm, &payer, &mint, vrgda_mint_ata, 1_000_000_000);
+
+        let now = svm.get_sysvar::<Clock>().unix_timestamp;
+        let v0 = VrgdaV0 {
+            mint: mint.pubkey(),
+            total_supply: 1_000_000_000,
+            authority: authority.pubkey(),
+            target_price: 4 * ONE,
+            decay_constant_percent: 50,
+            tokens_sold: 0,
+            created_at_timestamp: now,
+            vrgda_start_timestamp: now,
+            buy_window_time: 60,
+            last_buy_timestamp: 0,
+            auction_ended: false,
+            schedule: Schedule::LinearSchedule { r: 1_000_000 },
+            current_price: 0,
+            bump,
+        };
+        helpers::write_v0_auction(&mut svm, &vrgda_pda, &v0);
+
+        // Only the auction's authority can migrate it.
+        assert!(helpers::migrate(&mut svm, &stranger, &vrgda_pda).is_err());
+
+        helpers::migrate(&mut svm, &authority, &vrgda_pda).unwrap();
+        let account = svm.get_account(&vrgda_pda).unwrap();
+        assert_eq!(account.data.len(), 8 + VRGDA::INIT_SPACE);
+        assert_eq!(
+            account.lamports,
+            svm.minimum_balance_for_rent_exemption(8 + VRGDA::INIT_SPACE)
+        );
+        let vrgda_data: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        assert_eq!(vrgda_data.version, VRGDA_VERSION);
+        assert_eq!(vrgda_data.mint, v0.mint);
+        assert_eq!(vrgda_data.authority, v0.authority);
+        assert_eq!(vrgda_data.creator, v0.authority);
+        assert_eq!(vrgda_data.total_supply, v0.total_supply);
+        assert_eq!(vrgda_data.target_price, v0.target_price);
+        assert_eq!(vrgda_data.decay_constant_percent, v0.decay_constant_percent);
+        assert_eq!(vrgda_data.bump, bump);
+        assert!(vrgda_data.legacy_seeds);
+        assert_ne!(vrgda_data.q, 0);
+        assert_eq!(vrgda_data.reserved, [0; VRGDA_RESERVED_BYTES]);
+
+        svm.expire_blockhash();
+        assert!(helpers::migrate(&mut svm, &authority, &vrgda_pda).is_err());
+
+        // The migrated auction still signs for its vault.
+        helpers::buy_tokens(
+            &mut svm,
+            &payer,
+            &buyer,
+            &authority,
+            &vrgda_pda,
+            &vrgda_sol_ata,
+            &vrgda_mint_ata,
+            &mint,
+            &wsol_mint,
+            1_000_000,
+        );
+        let vrgda_data: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        assert_eq!(vrgda_data.tokens_sold, 1_000_000);
+    }
+
+    #[test]
+    fn test_pricing_constants() {
+        let mut fixture = helpers::AuctionFixture::new();
+
+        fixture.initialize(helpers::default_auction_params());
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            buyer,
+            vrgda_pda,
+            vrgda_mint_ata,
+            vrgda_sol_ata,
+            ..
+        } = fixture;
+
+        // ln(1 − 0.5) = −ln 2, and q = 2^(1/r) sits just above one.
+        let close = |value: u128, expected: u128| value.abs_diff(expected) < 1_000_000;
+        let vrgda_data: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        assert!(close(vrgda_data.ln_one_minus_k, 693_147_180_559_945_309));
+        assert!(close(vrgda_data.q, 1_000_000_693_147_420_786));
+
+        // New parameters come with new constants: ln(1 − 0.2) = −0.2231…
+        helpers::update_params(&mut svm, &authority, &vrgda_pda, 4 * ONE, 20, 1_000_000).unwrap();
+        let vrgda_data: VRGDA = helpers::fetch_account_data(&mut svm, &vrgda_pda);
+        assert_eq!(vrgda_data.decay_constant_percent, 20);
+        assert!(close(vrgda_data.ln_one_minus_k, 223_143_551_314_209_755));
+        assert!(close(vrgda_data.q, 1_000_000_223_143_576_210));
+
+        let metadata = helpers::buy_tokens_for(
+            &mut svm,
+            &payer,
+            &buyer,
+            &buyer.pubkey(),
+            &authority,
+            &vrgda_pda,
+            &vrgda_sol_ata,
+            &vrgda_mint_ata,
+            &mint,
+            &wsol_mint,
+            1_000_000,
+            false,
+        )
+        .unwrap();
+        // The pricing constants keep a plain `buy` within the default budget.
+        assert!(metadata.compute_units_consumed <= helpers::BUY_COMPUTE_UNITS as u64);
+
+        // Once something has sold, the curve is fixed.
+        assert!(
+            helpers::update_params(&mut svm, &authority, &vrgda_pda, 4 * ONE, 50, 1_000_000)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_pricing_errors() {
+        let mut fixture = helpers::AuctionFixture::new();
+
+        fixture.initialize(helpers::default_auction_params());
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            buyer,
+            vrgda_pda,
+            vrgda_mint_ata,
+            vrgda_sol_ata,
+            ..
+        } = fixture;
+
+        let update = |svm: &mut LiteSVM, target_price: u128, decay: u64, r: u64| {
+            helpers::update_params(svm, &authority, &vrgda_pda, target_price, decay, r)
+        };
+        let buy = |svm: &mut LiteSVM, amount: u64| {
+            svm.expire_blockhash();
+            helpers::buy_tokens_for(
+                svm,
+                &payer,
+                &buyer,
+                &buyer.pubkey(),
+                &authority,
+                &vrgda_pda,
+                &vrgda_sol_ata,
+                &vrgda_mint_ata,
+                &mint,
+                &wsol_mint,
+                amount,
+                false,
+            )
+        };
+
+        helpers::assert_vrgda_error(
+            update(&mut svm, 4 * ONE, 50, 0),
+            VRGDAError::InvalidSchedule,
+        );
+        helpers::assert_vrgda_error(
+            update(&mut svm, ONE - 1, 50, 1_000_000),
+            VRGDAError::InvalidTargetPrice,
+        );
+        helpers::assert_vrgda_error(
+            update(&mut svm, 4 * ONE, 0, 1_000_000),
+            VRGDAError::NonNegativeDecayConstant,
+        );
+        helpers::assert_vrgda_error(
+            update(&mut svm, 4 * ONE, 100, 1_000_000),
+            VRGDAError::InvalidDecayConstant,
+        );
+        helpers::assert_vrgda_error(
+            update(&mut svm, u128::MAX, 50, 1_000_000),
+            VRGDAError::PriceOverflow,
+        );
+
+        // A clock behind the start of the auction.
+        let mut clock = svm.get_sysvar::<Clock>();
+        let start = clock.unix_timestamp;
+        clock.unix_timestamp = start - 60;
+        svm.set_sysvar::<Clock>(&clock);
+        helpers::assert_vrgda_error(buy(&mut svm, 1_000_000), VRGDAError::AuctionNotStarted);
+        clock.unix_timestamp = start;
+        svm.set_sysvar::<Clock>(&clock);
+
+        // Two tokens at close to u64::MAX each cost more than a u64 can hold.
+        update(&mut svm, 10_000_000_000_000_000_000 * ONE, 50, 1_000_000).unwrap();
+        helpers::assert_vrgda_error(buy(&mut svm, 2_000_000), VRGDAError::PriceOverflow);
+    }
+
+    #[test]
+    fn test_verbose_logs_compute() {
+        // One buy against the given build of the program.
+        fn buy_with(program: &str) -> litesvm::types::TransactionMetadata {
+            let mut fixture = helpers::AuctionFixture::new();
+            fixture
+                .svm
+                .add_program_from_file(vrgda_exp::ID, program)
+                .expect("Failed to load VRGDA program");
+
+            fixture.initialize(helpers::default_auction_params());
+            let helpers::AuctionFixture {
+                mut svm,
+                payer,
+                mint,
+                wsol_mint,
+                authority,
+                buyer,
+                vrgda_pda,
+                vrgda_sol_ata,
+                vrgda_mint_ata,
+                ..
+            } = fixture;
+
+            helpers::buy_tokens_for(
+                &mut svm,
+                &payer,
+                &buyer,
+                &buyer.pubkey(),
+                &authority,
+                &vrgda_pda,
+                &vrgda_sol_ata,
+                &vrgda_mint_ata,
+                &mint,
+                &wsol_mint,
+                1_000_000,
+                false,
+            )
+            .unwrap()
+        }
+
+        // Built by `yarn build:svm`, see the README.
+        let verbose = buy_with("../target/deploy/vrgda_exp_verbose.so");
+        let quiet = buy_with("../target/deploy/vrgda_exp.so");
+
+        let traced = |logs: &[String]| logs.iter().any(|log| log.contains("Price in SOL"));
+        assert!(traced(&verbose.logs));
+        assert!(!traced(&quiet.logs));
+        assert!(quiet.compute_units_consumed < verbose.compute_units_consumed);
+    }
+
+    #[test]
+    fn test_initialize_rejects_invalid_params() {
+        let mut svm = LiteSVM::new();
+
+        svm.add_program_from_file(vrgda_exp::ID, "../target/deploy/vrgda_exp.so")
+            .expect("Failed to load VRGDA program");
+
+        let payer = Keypair::new();
+        let wsol_mint = Keypair::new();
+        let authority = Keypair::new();
+
+        svm.airdrop(&payer.pubkey(), 100_000_000_000).unwrap();
+        svm.airdrop(&authority.pubkey(), 100_000_000_000).unwrap();
+        helpers::init_mint_account(&mut svm, &payer, &wsol_mint);
+        let _ = helpers::initialize_ata(&mut svm, &payer, wsol_mint.pubkey(), authority.pubkey());
+
+        let mut initialize =
+            |target_price: u128, total_supply: u64, name: &str, symbol: &str, uri: &str| {
+                helpers::initialize_vrgda_with_mint(
+                    &mut svm,
+                    &authority,
+                    &wsol_mint.pubkey(),
+                    target_price,
+                    total_supply,
+                    name,
+                    symbol,
+                    uri,
+                    9,
+                    MetadataMode::Token2022,
+                    0,
+                )
+            };
+        let name = "vrgda token";
+        let uri = "https://example.com/metadata.json";
+
+        helpers::assert_vrgda_error(
+            initialize(ONE - 1, 1_000_000_000, name, "VRGDA", uri),
+            VRGDAError::InvalidTargetPrice,
+        );
+        helpers::assert_vrgda_error(
+            initialize(4 * ONE, 0, name, "VRGDA", uri),
+            VRGDAError::InvalidTotalSupply,
+        );
+        helpers::assert_vrgda_error(
+            initialize(4 * ONE, 1_000_000_000, &"n".repeat(33), "VRGDA", uri),
+            VRGDAError::MetadataTooLong,
+        );
+        helpers::assert_vrgda_error(
+            initialize(4 * ONE, 1_000_000_000, name, "VRGDATOKENS", uri),
+            VRGDAError::MetadataTooLong,
+        );
+        helpers::assert_vrgda_error(
+            initialize(4 * ONE, 1_000_000_000, name, "VRGDA", &"u".repeat(201)),
+            VRGDAError::MetadataTooLong,
+        );
+
+        initialize(4 * ONE, 1_000_000_000, name, "VRGDA", uri).unwrap();
+    }
+
+    #[test]
+    fn test_buy_through_cpi() {
+        let mut fixture = helpers::AuctionFixture::new();
+        let owner = Keypair::new();
+
+        fixture.initialize(helpers::default_auction_params());
+        let helpers::AuctionFixture {
+            mut svm,
+            payer,
+            mint,
+            wsol_mint,
+            authority,
+            vrgda_pda,
+            ..
+        } = fixture;
+
+        // The caller's wallet PDA only holds SOL and quote tokens. Its token
+        // account of the sale mint and its buyer record are created by `buy`,
+        // paid for by the PDA.
+        let wallet = helpers::get_caller_wallet_address(&owner.pubkey());
+        svm.airdrop(&owner.pubkey(), 1_000_000_000).unwrap();
+        svm.airdrop(&wallet, 100_000_000_000).unwrap();
+        helpers::initialize_ata(&mut svm, &payer, wsol_mint.pubkey(), wallet).unwrap();
+        let wallet_wsol_ata =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &wallet,
+                &wsol_mint.pubkey(),
+                &spl_token_2022::ID,
+            );
+        let wallet_ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+            &wallet,
+            &mint.pubkey(),
+            &spl_token_2022::ID,
+        );
+        helpers::mint_tokens(
+            &mut svm,
+            &payer,
+            &wsol_mint,
+            wallet_wsol_ata,
+            1_000_000_000_000,
+        );
+        assert!(svm.get_account(&wallet_ata).is_none());
+
+        let metadata =
+            helpers::quote(&mut svm, &payer, &vrgda_pda, &wsol_mint.pubkey(), 1_000_000).unwrap();
+        assert_eq!(metadata.return_data.program_id, vrgda_exp::ID);
+        let quote = PurchaseReceipt::try_from_slice(&metadata.return_data.data).unwrap();
+        assert_eq!(quote.amount, 1_000_000);
+        assert!(quote.cost >= quote.price && quote.price > 0);
+
+        let buy = |svm: &mut LiteSVM, max_cost: u64| {
+            helpers::caller_buy(
+                svm,
+                &owner,
+                &authority.pubkey(),
+                &vrgda_pda,
+                &mint.pubkey(),
+                &wsol_mint.pubkey(),
+                1_000_000,
+                max_cost,
+            )
+        };
+        assert!(buy(&mut svm, quote.cost - 1).is_err());
+
+        let metadata = buy(&mut svm, quote.cost).unwrap();
+        assert_eq!(metadata.return_data.program_id, vrgda_caller::ID);
+        let receipt = PurchaseReceipt::try_from_slice(&metadata.return_data.data).unwrap();
+        assert_eq!(receipt, quote);
+
+        assert_eq!(helpers::get_token_balance(&svm, &wallet_ata), 1_000_000);
+        assert_eq!(
+            helpers::get_token_balance(&svm, &wallet_wsol_ata),
+            1_000_000_000_000 - receipt.cost
+        );
+        let record: BuyerRecord = helpers::fetch_account_data(
+            &mut svm,
+            &helpers::get_buyer_record_address(&vrgda_pda, &wallet),
+        );
+        assert_eq!(record.buyer, wallet);
+        assert_eq!(record.tokens_bought, 1_000_000);
+    }
+
     #[test]
     #[ignore]
     fn test_pricing_fn() {
@@ -0,0 +1,132 @@
+use anchor_lang::prelude::*;
+use vrgda_exp::{cpi, cpi_helpers, program::Vrgda, state::PurchaseReceipt};
+
+declare_id!("Eaxe9ZTRhLLtwhGstXKzxC9krJV3rFMr2oTRVz89iqQ1");
+
+/// Mock program that buys from a VRGDA through CPI. Its wallet PDA is buyer and
+/// recipient, so the auction sees a PDA signer paying for the accounts it creates.
+#[program]
+pub mod vrgda_caller {
+    use super::*;
+
+    /// Quotes `amount` and buys it into the owner's wallet if it costs at most
+    /// `max_cost`. The auction's receipt is passed on through return data.
+    pub fn buy(ctx: Context<Buy>, amount: u64, max_cost: u64) -> Result<PurchaseReceipt> {
+        let quote = cpi_helpers::quote(
+            CpiContext::new(
+                ctx.accounts.vrgda_program.to_account_info(),
+                cpi::accounts::Quote {
+                    vrgda: ctx.accounts.vrgda.to_account_info(),
+                    wsol_mint: ctx.accounts.wsol_mint.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        require_gte!(max_cost, quote.cost, CallerError::TooExpensive);
+
+        let owner = ctx.accounts.owner.key();
+        let wallet_seeds = &[b"wallet".as_ref(), owner.as_ref(), &[ctx.bumps.wallet]];
+        let signer = &[&wallet_seeds[..]];
+
+        let receipt = cpi_helpers::buy(
+            CpiContext::new_with_signer(
+                ctx.accounts.vrgda_program.to_account_info(),
+                cpi::accounts::Buy {
+                    buyer: ctx.accounts.wallet.to_account_info(),
+                    recipient: ctx.accounts.wallet.to_account_info(),
+                    vrgda: ctx.accounts.vrgda.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    wsol_mint: ctx.accounts.wsol_mint.to_account_info(),
+                    buyer_wsol_ata: ctx.accounts.wallet_wsol_ata.to_account_info(),
+                    recipient_ata: ctx.accounts.wallet_ata.to_account_info(),
+                    vrgda_vault: ctx.accounts.vrgda_vault.to_account_info(),
+                    vrgda_sol_ata: ctx.accounts.vrgda_sol_ata.to_account_info(),
+                    proceeds_vault: ctx
+                        .accounts
+                        .proceeds_vault
+                        .as_ref()
+                        .map(|vault| vault.to_account_info()),
+                    buyer_record: ctx
+                        .accounts
+                        .buyer_record
+                        .as_ref()
+                        .map(|record| record.to_account_info()),
+                    vesting_escrow: None,
+                    vesting_vault: None,
+                    payout: ctx.accounts.payout.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    associated_token_program: ctx
+                        .accounts
+                        .associated_token_program
+                        .to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+        require!(receipt == quote, CallerError::QuoteMismatch);
+        Ok(receipt)
+    }
+}
+
+#[derive(Accounts)]
+pub struct Buy<'info> {
+    pub owner: Signer<'info>,
+
+    /// Funded system account that buys, pays and receives the tokens.
+    #[account(
+        mut,
+        seeds = [b"wallet".as_ref(), owner.key().as_ref()],
+        bump,
+    )]
+    pub wallet: SystemAccount<'info>,
+
+    /// CHECK: checked by the auction program
+    #[account(mut)]
+    pub vrgda: UncheckedAccount<'info>,
+    /// CHECK: checked by the auction program
+    #[account(mut)]
+    pub mint: UncheckedAccount<'info>,
+    /// CHECK: checked by the auction program
+    pub wsol_mint: UncheckedAccount<'info>,
+    /// CHECK: checked by the auction program
+    #[account(mut)]
+    pub wallet_wsol_ata: UncheckedAccount<'info>,
+    /// CHECK: created by the auction program if needed
+    #[account(mut)]
+    pub wallet_ata: UncheckedAccount<'info>,
+    /// CHECK: checked by the auction program
+    #[account(mut)]
+    pub vrgda_vault: UncheckedAccount<'info>,
+    /// CHECK: checked by the auction program
+    #[account(mut)]
+    pub vrgda_sol_ata: UncheckedAccount<'info>,
+    /// CHECK: checked by the auction program
+    #[account(mut)]
+    pub proceeds_vault: Option<UncheckedAccount<'info>>,
+    /// CHECK: created by the auction program if needed
+    #[account(mut)]
+    pub buyer_record: Option<UncheckedAccount<'info>>,
+    /// CHECK: checked by the auction program
+    pub payout: UncheckedAccount<'info>,
+
+    /// CHECK: checked by the auction program
+    pub token_program: UncheckedAccount<'info>,
+    /// CHECK: checked by the auction program
+    pub associated_token_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    pub vrgda_program: Program<'info, Vrgda>,
+}
+
+#[error_code]
+pub enum CallerError {
+    #[msg("Purchase costs more than the caller allows")]
+    TooExpensive,
+
+    #[msg("Auction charged something other than it quoted")]
+    QuoteMismatch,
+}
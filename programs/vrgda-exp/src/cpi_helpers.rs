@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::get_return_data;
+
+use crate::{cpi, error::VRGDAError, state::PurchaseReceipt};
+
+/// Buys through `buy` and returns its receipt. The buyer can be a PDA of the
+/// calling program, signed for with `CpiContext::new_with_signer`. It pays for
+/// the token accounts and records `buy` creates, so it has to be a funded
+/// system account.
+pub fn buy<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, cpi::accounts::Buy<'info>>,
+    amount: u64,
+) -> Result<PurchaseReceipt> {
+    cpi::buy(ctx, amount)?;
+    receipt()
+}
+
+/// Prices a purchase through `quote` without buying.
+pub fn quote<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, cpi::accounts::Quote<'info>>,
+    amount: u64,
+) -> Result<PurchaseReceipt> {
+    cpi::quote(ctx, amount)?;
+    receipt()
+}
+
+/// Reads the receipt the last call into this program left in return data.
+/// Unlike `cpi::Return::get`, this fails instead of panicking when the data is
+/// missing or was set by another program.
+fn receipt() -> Result<PurchaseReceipt> {
+    let (program_id, data) = get_return_data().ok_or(VRGDAError::MissingReceipt)?;
+    require_keys_eq!(program_id, crate::ID, VRGDAError::MissingReceipt);
+    PurchaseReceipt::try_from_slice(&data).map_err(|_| VRGDAError::MissingReceipt.into())
+}
@@ -43,4 +43,175 @@ pub enum VRGDAError {
 
     #[msg("Decay constant should be negative")]
     NonNegativeDecayConstant,
+
+    #[msg("Too many payees")]
+    TooManyPayees,
+
+    #[msg("Payee shares must be non-zero and add up to 10000 basis points")]
+    InvalidPayeeShares,
+
+    #[msg("Payee listed more than once")]
+    DuplicatePayee,
+
+    #[msg("Payee token account does not match the payee list")]
+    PayeeAccountMismatch,
+
+    #[msg("Vesting cliff must be within a positive duration")]
+    InvalidVestingConfig,
+
+    #[msg("Vesting escrow accounts are required for this auction")]
+    VestingAccountsMissing,
+
+    #[msg("No vested tokens to claim")]
+    NothingToClaim,
+
+    #[msg("Reserve amount must be greater than zero")]
+    InvalidReserve,
+
+    #[msg("Reserve escrow accounts are required when a reserve is configured")]
+    ReserveAccountsMissing,
+
+    #[msg("Soft cap must be non-zero with a deadline after the auction start")]
+    InvalidSoftCap,
+
+    #[msg("Soft cap has not been met")]
+    SoftCapNotMet,
+
+    #[msg("Soft cap deadline passed without meeting the cap")]
+    SoftCapFailed,
+
+    #[msg("A buyer record is required for this auction")]
+    BuyerRecordMissing,
+
+    #[msg("Metaplex metadata accounts are required for this metadata mode")]
+    MetadataAccountsMissing,
+
+    #[msg("Token-2022 metadata needs a Token-2022 mint whose metadata pointer targets itself")]
+    MetadataPointerMismatch,
+
+    #[msg("Metadata has been locked")]
+    MetadataLocked,
+
+    #[msg("Instruction does not match the auction's mint mode")]
+    MintModeMismatch,
+
+    #[msg("Edition name, symbol or uri is too long for the number of editions")]
+    InvalidEditionConfig,
+
+    #[msg("Collection accounts are required when the drop has a collection")]
+    CollectionAccountsMissing,
+
+    #[msg("Mint authority is held by another auction, only a funded pre-mint auction is possible")]
+    MintAuthorityUnavailable,
+
+    #[msg("Signer is not the proposed authority")]
+    NotPendingAuthority,
+
+    #[msg("Soft-capped, vesting and cooldown auctions only sell through buy")]
+    PerBuyerAccountsRequired,
+
+    #[msg("Batch recipient must be a token account of the sale mint")]
+    InvalidBatchRecipient,
+
+    #[msg("Current price is above the order's limit")]
+    LimitPriceNotReached,
+
+    #[msg("DCA orders need a non-zero amount, interval and slice count")]
+    InvalidDcaConfig,
+
+    #[msg("Next DCA slice is not due yet")]
+    DcaSliceNotDue,
+
+    #[msg("Purchase intent is not signed by the buyer")]
+    InvalidIntentSignature,
+
+    #[msg("Purchase intent has expired")]
+    IntentExpired,
+
+    #[msg("Purchase intent is for a different auction")]
+    IntentMismatch,
+
+    #[msg("Purchase costs more than the intent allows")]
+    IntentCostExceeded,
+
+    #[msg("Rate limits must be greater than zero")]
+    InvalidRateLimits,
+
+    #[msg("Amount exceeds the per-transaction limit")]
+    TransactionLimitExceeded,
+
+    #[msg("Amount exceeds what is left of this slot's limit")]
+    SlotLimitExceeded,
+
+    #[msg("Wallet has to wait for its cooldown before buying again")]
+    WalletCooldownActive,
+
+    #[msg("Commit-reveal needs a non-zero delay shorter than the timeout")]
+    InvalidCommitRevealConfig,
+
+    #[msg("Auction only sells through commit and reveal")]
+    CommitRevealRequired,
+
+    #[msg("Auction does not take commits")]
+    CommitRevealDisabled,
+
+    #[msg("Revealed purchase does not match the commitment")]
+    CommitMismatch,
+
+    #[msg("Commit cannot be revealed yet")]
+    RevealTooEarly,
+
+    #[msg("Commit has expired and can only be reclaimed")]
+    CommitExpired,
+
+    #[msg("Commit can still be revealed")]
+    CommitNotExpired,
+
+    #[msg("Purchase costs more than the committed max")]
+    CommitCostExceeded,
+
+    #[msg("Account is already in the current layout")]
+    AlreadyMigrated,
+
+    #[msg("Account layout version is not supported")]
+    UnsupportedAccountVersion,
+
+    #[msg("Account has to be migrated to the current layout first")]
+    AccountNotMigrated,
+
+    #[msg("Pricing parameters can only change before the first sale")]
+    ParamsLocked,
+
+    #[msg("Auction has not started yet")]
+    AuctionNotStarted,
+
+    #[msg("Schedule rate must be greater than zero")]
+    InvalidSchedule,
+
+    #[msg("Price does not fit in a u64")]
+    PriceOverflow,
+
+    #[msg("Power error")]
+    PowError,
+
+    #[msg("Target price must be at least one lamport")]
+    InvalidTargetPrice,
+
+    #[msg("Total supply must be greater than zero")]
+    InvalidTotalSupply,
+
+    #[msg("Name, symbol or uri is too long")]
+    MetadataTooLong,
+
+    #[msg("Auction program returned no purchase receipt")]
+    MissingReceipt,
+
+    #[msg("Distribute the proceeds vault before closing the auction")]
+    ProceedsNotDistributed,
+
+    #[msg("Buyers have refunds to claim before the auction can close")]
+    RefundsOutstanding,
+
+    #[msg("Proceeds vault is required exactly when the auction has payees or a soft cap")]
+    ProceedsVaultMismatch,
 }
@@ -0,0 +1,178 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        metadata_pointer::MetadataPointer, transfer_fee::TransferFeeConfig, transfer_hook,
+        BaseStateWithExtensions, StateWithExtensions,
+    },
+    state::Mint,
+};
+use anchor_spl::token_interface::{
+    spl_pod::optional_keys::OptionalNonZeroPubkey,
+    spl_token_metadata_interface::state::TokenMetadata,
+};
+
+use crate::error::VRGDAError;
+
+/// Fee withheld by the mint's transfer-fee extension when `amount` is sent in
+/// the current epoch. Zero for mints without the extension.
+pub fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => Ok(config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(VRGDAError::MathOverflow)?),
+        Err(_) => Ok(0),
+    }
+}
+
+/// Amount that has to be sent so that exactly `net_amount` arrives after the
+/// transfer fee.
+pub fn gross_up(mint: &AccountInfo, net_amount: u64) -> Result<u64> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    let fee = match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, net_amount)
+            .ok_or(VRGDAError::MathOverflow)?,
+        Err(_) => 0,
+    };
+    Ok(net_amount
+        .checked_add(fee)
+        .ok_or(VRGDAError::MathOverflow)?)
+}
+
+/// True when transfers of `mint` go through a transfer-hook program.
+pub fn has_transfer_hook(mint: &AccountInfo) -> Result<bool> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    Ok(transfer_hook::get_program_id(&state).is_some())
+}
+
+/// Account the mint's metadata-pointer extension points at, if it has one.
+pub fn metadata_pointer(mint: &AccountInfo) -> Result<Option<Pubkey>> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    Ok(state
+        .get_extension::<MetadataPointer>()
+        .ok()
+        .and_then(|pointer| Option::<Pubkey>::from(pointer.metadata_address)))
+}
+
+/// True when the mint already carries the token-metadata extension.
+pub fn has_token_metadata(mint: &AccountInfo) -> Result<bool> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    Ok(state.get_variable_len_extension::<TokenMetadata>().is_ok())
+}
+
+/// Update authority of the mint's token-metadata extension, `None` once the
+/// metadata is locked.
+pub fn token_metadata_update_authority(mint: &AccountInfo) -> Result<Option<Pubkey>> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    let metadata = state.get_variable_len_extension::<TokenMetadata>()?;
+    Ok(Option::<Pubkey>::from(metadata.update_authority))
+}
+
+/// Bytes the token-metadata extension takes up on the mint for these fields.
+pub fn token_metadata_space(
+    mint: Pubkey,
+    update_authority: Pubkey,
+    name: &str,
+    symbol: &str,
+    uri: &str,
+) -> Result<usize> {
+    let metadata = TokenMetadata {
+        update_authority: OptionalNonZeroPubkey::try_from(Some(update_authority))?,
+        mint,
+        name: name.to_string(),
+        symbol: symbol.to_string(),
+        uri: uri.to_string(),
+        additional_metadata: Vec::new(),
+    };
+    Ok(metadata.tlv_size_of()?)
+}
+
+/// Account length the mint needs once its token metadata holds the given
+/// fields. `None` keeps the current value.
+pub fn updated_token_metadata_len(
+    mint: &AccountInfo,
+    name: Option<&str>,
+    symbol: Option<&str>,
+    uri: Option<&str>,
+) -> Result<usize> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    let mut metadata = state.get_variable_len_extension::<TokenMetadata>()?;
+    let current_size = metadata.tlv_size_of()?;
+    if let Some(name) = name {
+        metadata.name = name.to_string();
+    }
+    if let Some(symbol) = symbol {
+        metadata.symbol = symbol.to_string();
+    }
+    if let Some(uri) = uri {
+        metadata.uri = uri.to_string();
+    }
+    Ok(data
+        .len()
+        .checked_add(metadata.tlv_size_of()?)
+        .and_then(|len| len.checked_sub(current_size))
+        .ok_or(VRGDAError::MathOverflow)?)
+}
+
+/// Tops up `account` from `payer` so it stays rent exempt at `len` bytes. The
+/// token program reallocs for metadata changes but leaves the funding to us.
+pub fn fund_realloc<'info>(
+    system_program: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    len: usize,
+) -> Result<()> {
+    let top_up = Rent::get()?
+        .minimum_balance(len)
+        .saturating_sub(account.lamports());
+    if top_up > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program,
+                anchor_lang::system_program::Transfer {
+                    from: payer,
+                    to: account,
+                },
+            ),
+            top_up,
+        )?;
+    }
+    Ok(())
+}
+
+/// `transfer_checked` that resolves the mint's transfer-hook accounts from
+/// `hook_accounts`, usually the instruction's `remaining_accounts`.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_checked<'info>(
+    token_program: &AccountInfo<'info>,
+    from: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    hook_accounts: &[AccountInfo<'info>],
+    amount: u64,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    spl_token_2022::onchain::invoke_transfer_checked(
+        token_program.key,
+        from,
+        mint,
+        to,
+        authority,
+        hook_accounts,
+        amount,
+        decimals,
+        signer_seeds,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    ed25519_program,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+
+use crate::error::VRGDAError;
+
+/// Start of the first signature's offsets in an ed25519 precompile instruction,
+/// after the signature count and a padding byte.
+const OFFSETS_START: usize = 2;
+/// Size of one set of signature offsets.
+const OFFSETS_LEN: usize = 14;
+/// Instruction index the precompile uses for "the data of this instruction".
+const THIS_INSTRUCTION: u16 = u16::MAX;
+
+/// Checks that the instruction right before the current one is an ed25519
+/// precompile call over exactly `message` signed by `signer`. The runtime fails
+/// the whole transaction when the precompile rejects a signature, so finding the
+/// instruction is enough.
+pub fn verify_ed25519_signature(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let current = load_current_index_checked(instructions)? as usize;
+    require!(current > 0, VRGDAError::InvalidIntentSignature);
+    let ix = load_instruction_at_checked(current - 1, instructions)?;
+    require_keys_eq!(
+        ix.program_id,
+        ed25519_program::ID,
+        VRGDAError::InvalidIntentSignature
+    );
+
+    let data = &ix.data;
+    require!(
+        data.len() >= OFFSETS_START + OFFSETS_LEN && data[0] == 1,
+        VRGDAError::InvalidIntentSignature
+    );
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let signature_ix = read_u16(OFFSETS_START + 2);
+    let pubkey_offset = read_u16(OFFSETS_START + 4) as usize;
+    let pubkey_ix = read_u16(OFFSETS_START + 6);
+    let message_offset = read_u16(OFFSETS_START + 8) as usize;
+    let message_len = read_u16(OFFSETS_START + 10) as usize;
+    let message_ix = read_u16(OFFSETS_START + 12);
+
+    // Everything has to come from the precompile instruction itself, otherwise
+    // the signature could be over data we never look at.
+    require!(
+        signature_ix == THIS_INSTRUCTION
+            && pubkey_ix == THIS_INSTRUCTION
+            && message_ix == THIS_INSTRUCTION,
+        VRGDAError::InvalidIntentSignature
+    );
+    let signed_pubkey = data
+        .get(pubkey_offset..pubkey_offset + 32)
+        .ok_or(VRGDAError::InvalidIntentSignature)?;
+    let signed_message = data
+        .get(message_offset..message_offset + message_len)
+        .ok_or(VRGDAError::InvalidIntentSignature)?;
+    require!(
+        signed_pubkey == signer.as_ref() && signed_message == message,
+        VRGDAError::InvalidIntentSignature
+    );
+    Ok(())
+}
@@ -1,344 +1,2032 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface},
+    associated_token::{self, AssociatedToken},
+    metadata::{
+        create_metadata_accounts_v3, update_metadata_accounts_v2, CreateMetadataAccountsV3,
+        UpdateMetadataAccountsV2,
+    },
+    token::Token,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{ExtensionType, StateWithExtensions},
+    },
+    token_interface::{
+        spl_pod::optional_keys::OptionalNonZeroPubkey, Mint, TokenAccount, TokenInterface,
+    },
+};
+use mpl_token_metadata::{
+    types::{Collection, Data, DataV2},
+    ID as METADATA_PROGRAM_ID,
 };
-use mpl_token_metadata::{types::Data, ID as METADATA_PROGRAM_ID};
 
 use anchor_spl::token_interface;
+
+/// `msg!` for the step-by-step pricing trace. Only logs with the
+/// `verbose-logs` feature, formatting these numbers costs more compute than the
+/// math itself.
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "verbose-logs") {
+            msg!($($arg)*);
+        }
+    };
+}
+
+#[cfg(feature = "cpi")]
+pub mod cpi_helpers;
 pub mod error;
+pub mod extensions;
+pub mod intent;
 pub mod math;
 pub mod state;
 
 use error::VRGDAError;
-use math::cast::Cast;
-use state::{Schedule, VRGDA};
+use math::{cast::Cast, ONE};
+use state::{
+    AuctionParams, BatchPurchase, BuyerRecord, CommitRevealConfig, DcaOrder, EditionDropParams,
+    IntentNonce, LimitOrder, MetadataMode, MintMode, PurchaseCommit, PurchaseIntent,
+    PurchaseReceipt, RateLimits, Schedule, VestingEscrow, VrgdaV0, VRGDA, VRGDA_V0_SPACE,
+    VRGDA_VERSION,
+};
 
 declare_id!("FLSsuUZXKnDYyfhjTF1GTkvFkyQctfxABEEjGZxc5FJZ");
 
 #[program]
 pub mod vrgda {
 
-    use crate::math::{to_actual_mint_amount, ONE};
     use anchor_spl::{
-        metadata::{create_metadata_accounts_v3, CreateMetadataAccountsV3},
-        token_2022::TransferChecked,
+        metadata::{
+            create_master_edition_v3, verify_sized_collection_item, CreateMasterEditionV3,
+            VerifySizedCollectionItem,
+        },
+        token_interface::spl_token_metadata_interface::state::Field,
     };
-    use mpl_token_metadata::types::DataV2;
 
     use super::*;
 
-    pub fn initialize_vrgda(
-        ctx: Context<Initialize>,
-        target_price: u128,
-        decay_constant_percent: u64,
-        vrgda_start_timestamp: i64,
-        total_supply: u64,
-        r: u64,
-        name: String,
-        symbol: String,
-        uri: String,
+    /// `remaining_accounts` carries the extra accounts of any transfer hook on the
+    /// sale or quote mint.
+    pub fn initialize_vrgda<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Initialize<'info>>,
+        params: AuctionParams,
     ) -> Result<()> {
-        // let vrgda = &mut ctx.accounts.vrgda;
-        ctx.accounts.vrgda.total_supply = total_supply;
-        ctx.accounts.vrgda.target_price = target_price;
-        ctx.accounts.vrgda.decay_constant_percent = decay_constant_percent;
-        ctx.accounts.vrgda.schedule = Schedule::LinearSchedule { r };
-        ctx.accounts.vrgda.tokens_sold = 0;
-        ctx.accounts.vrgda.auction_ended = false;
-        ctx.accounts.vrgda.current_price = target_price.checked_div(ONE).unwrap() as u64;
-        ctx.accounts.vrgda.buy_window_time = 60; // 60 seconds hardcoded for now
-        ctx.accounts.vrgda.created_at_timestamp = Clock::get()?.unix_timestamp.cast::<i64>()?;
-
-        ctx.accounts.vrgda.vrgda_start_timestamp =
-            if vrgda_start_timestamp < Clock::get()?.unix_timestamp.cast::<i64>()? {
-                Clock::get()?.unix_timestamp.cast::<i64>()?
-            } else {
-                vrgda_start_timestamp
-            };
-
-        ctx.accounts.vrgda.authority = ctx.accounts.authority.key();
-        ctx.accounts.vrgda.mint = ctx.accounts.mint.key();
-        ctx.accounts.vrgda.bump = ctx.bumps.vrgda;
-
-        let token_data = DataV2 {
-            name: name,
-            symbol: symbol,
-            uri: uri,
-            seller_fee_basis_points: 0,
-            creators: None,
-            collection: None,
-            uses: None,
-        };
-
-        let mint_seed = ctx.accounts.mint.key();
-        let authority_seed = ctx.accounts.authority.key();
-
-        let signer_seeds = &[
-            b"vrgda".as_ref(),
-            mint_seed.as_ref(),
-            authority_seed.as_ref(),
-            &[ctx.bumps.vrgda],
-        ];
-
-        let seeds = &[&signer_seeds[..]];
-
-        let metadata_ctx = CpiContext::new_with_signer(
-            ctx.accounts.metadata_program.to_account_info(),
-            CreateMetadataAccountsV3 {
-                metadata: ctx.accounts.metadata.to_account_info(),
+        setup_auction(
+            AuctionSetup {
+                authority: ctx.accounts.authority.to_account_info(),
+                vrgda: &mut ctx.accounts.vrgda,
+                vrgda_bump: ctx.bumps.vrgda,
+                vrgda_vault: ctx.accounts.vrgda_vault.to_account_info(),
                 mint: ctx.accounts.mint.to_account_info(),
-                mint_authority: ctx.accounts.authority.to_account_info(),
-                update_authority: ctx.accounts.authority.to_account_info(),
-                payer: ctx.accounts.authority.to_account_info(),
+                authority_token_account: ctx
+                    .accounts
+                    .authority_token_account
+                    .as_ref()
+                    .map(|account| account.to_account_info()),
+                wsol_mint: ctx.accounts.wsol_mint.to_account_info(),
+                proceeds_vault: ctx
+                    .accounts
+                    .proceeds_vault
+                    .as_ref()
+                    .map(|vault| vault.to_account_info()),
+                reserve_escrow: ctx.accounts.reserve_escrow.as_deref_mut(),
+                reserve_escrow_bump: ctx.bumps.reserve_escrow,
+                reserve_vault: ctx
+                    .accounts
+                    .reserve_vault
+                    .as_ref()
+                    .map(|vault| vault.to_account_info()),
+                metadata: ctx
+                    .accounts
+                    .metadata
+                    .as_ref()
+                    .map(|metadata| metadata.to_account_info()),
+                metadata_program: ctx
+                    .accounts
+                    .metadata_program
+                    .as_ref()
+                    .map(|program| program.to_account_info()),
+                token_program: ctx.accounts.token_program.to_account_info(),
                 system_program: ctx.accounts.system_program.to_account_info(),
                 rent: ctx.accounts.rent.to_account_info(),
+                hook_accounts: ctx.remaining_accounts,
             },
-            seeds,
-        );
+            params,
+        )
+    }
+
+    /// Same as `initialize_vrgda`, but creates the sale mint itself as a PDA of
+    /// the authority and symbol, with `decimals` under the given token program.
+    pub fn initialize_vrgda_with_mint(
+        ctx: Context<InitializeWithMint>,
+        params: AuctionParams,
+        decimals: u8,
+    ) -> Result<()> {
+        let authority_key = ctx.accounts.authority.key();
+        let mint_key = ctx.accounts.mint.key();
+        let mint_seeds = &[
+            b"mint".as_ref(),
+            authority_key.as_ref(),
+            params.symbol.as_bytes(),
+            &[ctx.bumps.mint],
+        ];
 
-        create_metadata_accounts_v3(metadata_ctx, token_data, false, true, None)?;
+        // Token-2022 metadata lives on the mint, which needs the pointer extension
+        // in place before it is initialized.
+        let mint_extensions = if params.metadata_mode == MetadataMode::Token2022 {
+            vec![ExtensionType::MetadataPointer]
+        } else {
+            Vec::new()
+        };
+        let space = ExtensionType::try_calculate_account_len::<
+            anchor_spl::token_2022::spl_token_2022::state::Mint,
+        >(&mint_extensions)?;
 
-        token_interface::set_authority(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                anchor_spl::token_interface::SetAuthority {
-                    current_authority: ctx.accounts.authority.to_account_info(),
-                    account_or_mint: ctx.accounts.mint.to_account_info(),
+        anchor_lang::system_program::create_account(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::CreateAccount {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: ctx.accounts.mint.to_account_info(),
                 },
+                &[&mint_seeds[..]],
             ),
-            anchor_spl::token_interface::spl_token_2022::instruction::AuthorityType::MintTokens,
-            Some(ctx.accounts.vrgda.key()),
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            &ctx.accounts.token_program.key(),
         )?;
 
-        token_interface::mint_to(
-            CpiContext::new_with_signer(
+        if params.metadata_mode == MetadataMode::Token2022 {
+            token_interface::metadata_pointer_initialize(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token_interface::MetadataPointerInitialize {
+                        token_program_id: ctx.accounts.token_program.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                    },
+                ),
+                Some(authority_key),
+                Some(mint_key),
+            )?;
+        }
+
+        // The authority starts out as mint authority and hands it to the VRGDA
+        // during setup, exactly like a caller-provided mint.
+        token_interface::initialize_mint2(
+            CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                anchor_spl::token_interface::MintTo {
+                token_interface::InitializeMint2 {
                     mint: ctx.accounts.mint.to_account_info(),
-                    to: ctx.accounts.vrgda_vault.to_account_info(),
-                    authority: ctx.accounts.vrgda.to_account_info(),
                 },
-                seeds,
             ),
-            total_supply,
+            decimals,
+            &authority_key,
+            None,
         )?;
 
-        msg!("VRGDA PDA: {:?}", ctx.accounts.vrgda.key());
-        msg!("VRGDA Mint: {:?}", ctx.accounts.vrgda.mint);
-        msg!("VRGDA Authority: {:?}", ctx.accounts.vrgda.authority);
-        msg!("VRGDA VAULT: {:?}", ctx.accounts.vrgda_vault.key());
-        msg!("VRGDA TOTAL SUPPLY: {}", ctx.accounts.vrgda.total_supply);
-        msg!("VRGDA TARGET PRICE: {}", ctx.accounts.vrgda.target_price);
-        msg!(
-            "VRGDA DECAY CONSTANT: {}",
-            ctx.accounts.vrgda.decay_constant_percent
-        );
-        msg!("VRGDA SCHEDULE: {:?}", ctx.accounts.vrgda.schedule);
-        msg!(
-            "VRGDA CREATED AT TIMESTAMP: {}",
-            ctx.accounts.vrgda.created_at_timestamp
-        );
-        msg!(
-            "VRDGA START TIMESTAMP: {}",
-            ctx.accounts.vrgda.vrgda_start_timestamp
+        associated_token::create(CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: ctx.accounts.authority.to_account_info(),
+                associated_token: ctx.accounts.vrgda_vault.to_account_info(),
+                authority: ctx.accounts.vrgda.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        ))?;
+
+        if params.reserve.is_some() {
+            let reserve_escrow = ctx
+                .accounts
+                .reserve_escrow
+                .as_ref()
+                .ok_or(VRGDAError::ReserveAccountsMissing)?;
+            let reserve_vault = ctx
+                .accounts
+                .reserve_vault
+                .as_ref()
+                .ok_or(VRGDAError::ReserveAccountsMissing)?;
+            associated_token::create(CpiContext::new(
+                ctx.accounts.associated_token_program.to_account_info(),
+                associated_token::Create {
+                    payer: ctx.accounts.authority.to_account_info(),
+                    associated_token: reserve_vault.to_account_info(),
+                    authority: reserve_escrow.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+            ))?;
+        }
+
+        setup_auction(
+            AuctionSetup {
+                authority: ctx.accounts.authority.to_account_info(),
+                vrgda: &mut ctx.accounts.vrgda,
+                vrgda_bump: ctx.bumps.vrgda,
+                vrgda_vault: ctx.accounts.vrgda_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority_token_account: None,
+                wsol_mint: ctx.accounts.wsol_mint.to_account_info(),
+                proceeds_vault: ctx
+                    .accounts
+                    .proceeds_vault
+                    .as_ref()
+                    .map(|vault| vault.to_account_info()),
+                reserve_escrow: ctx.accounts.reserve_escrow.as_deref_mut(),
+                reserve_escrow_bump: ctx.bumps.reserve_escrow,
+                reserve_vault: ctx
+                    .accounts
+                    .reserve_vault
+                    .as_ref()
+                    .map(|vault| vault.to_account_info()),
+                metadata: ctx
+                    .accounts
+                    .metadata
+                    .as_ref()
+                    .map(|metadata| metadata.to_account_info()),
+                metadata_program: ctx
+                    .accounts
+                    .metadata_program
+                    .as_ref()
+                    .map(|program| program.to_account_info()),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+                hook_accounts: &[],
+            },
+            params,
+        )
+    }
+
+    /// Sets up a drop that sells numbered 1-of-1 NFTs along the curve instead of
+    /// a fungible token. At most `max_editions` are sold, each through `buy_edition`.
+    pub fn initialize_edition_drop(
+        ctx: Context<InitializeEditionDrop>,
+        params: EditionDropParams,
+    ) -> Result<()> {
+        let EditionDropParams {
+            auction_id,
+            target_price,
+            decay_constant_percent,
+            vrgda_start_timestamp,
+            max_editions,
+            r,
+            payees,
+            edition,
+        } = params;
+
+        VRGDA::validate_params(target_price, decay_constant_percent, r)?;
+        VRGDA::validate_payees(&payees)?;
+        edition.validate(max_editions)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let vrgda = &mut ctx.accounts.vrgda;
+        vrgda.set_inner(VRGDA {
+            total_supply: max_editions,
+            current_price: u64::try_from(target_price / ONE)
+                .map_err(|_| VRGDAError::PriceOverflow)?,
+            payees,
+            mint_mode: MintMode::Editions,
+            edition: Some(edition),
+            auction_id,
+            ..VRGDA::new(
+                ctx.accounts.mint.key(),
+                ctx.accounts.authority.key(),
+                target_price,
+                decay_constant_percent,
+                Schedule::LinearSchedule { r },
+                now,
+                vrgda_start_timestamp.max(now),
+                ctx.bumps.vrgda,
+            )
+        });
+        vrgda.update_pricing_constants()?;
+        require!(
+            ctx.accounts.proceeds_vault.is_some() == vrgda.escrows_proceeds(),
+            VRGDAError::ProceedsVaultMismatch
         );
+
+        msg!("VRGDA PDA: {:?}", vrgda.key());
+        msg!("VRGDA MAX EDITIONS: {}", vrgda.total_supply);
+        msg!("VRGDA TARGET PRICE: {}", vrgda.target_price);
+        msg!("VRGDA EDITION: {:?}", vrgda.edition);
         Ok(())
     }
 
-    pub fn buy(ctx: Context<Buy>, amount: u64) -> Result<()> {
+    /// Sells `amount` tokens to `recipient` at the current price. The receipt is
+    /// returned through return data, see `cpi_helpers::buy`.
+    pub fn buy<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Buy<'info>>,
+        amount: u64,
+    ) -> Result<PurchaseReceipt> {
         require!(amount != 0, VRGDAError::AmountCantBeZero);
-        {
-            // First, update the VRGDA state in its own scope.
-            let vrgda = &mut ctx.accounts.vrgda;
+        require!(
+            ctx.accounts.vrgda.mint_mode != MintMode::Editions,
+            VRGDAError::MintModeMismatch
+        );
+        // Pre-minted tokens leave the vault through a transfer, so the vault also
+        // pays the sale mint's transfer fee for the buyer to receive `amount`.
+        let tokens_out = if ctx.accounts.vrgda.mint_mode == MintMode::PreMint {
+            extensions::gross_up(&ctx.accounts.mint.to_account_info(), amount)?
+        } else {
+            amount
+        };
+        book_purchase(&mut ctx.accounts.vrgda, amount, tokens_out)?;
+
+        let vrgda = &ctx.accounts.vrgda;
+        // Now, create the signer seeds using the (immutable) account data.
+        let vrgda_signer = vrgda.signer();
+
+        let payment_destination = proceeds_destination(
+            vrgda,
+            ctx.accounts.vrgda_sol_ata.to_account_info(),
+            ctx.accounts.proceeds_vault.as_deref(),
+        )?;
+
+        let cost = collect_payment(
+            Payment {
+                buyer: ctx.accounts.buyer.to_account_info(),
+                buyer_wsol_ata: ctx.accounts.buyer_wsol_ata.to_account_info(),
+                wsol_mint: &ctx.accounts.wsol_mint,
+                destination: payment_destination,
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                hook_accounts: ctx.remaining_accounts,
+            },
+            vrgda.current_price,
+        )?;
+
+        // Refunds need to know what each recipient paid for and received, the
+        // cooldown when they last bought.
+        if let Some(record) = ctx.accounts.buyer_record.as_mut() {
+            if record.vrgda == Pubkey::default() {
+                record.vrgda = vrgda.key();
+                record.buyer = ctx.accounts.recipient.key();
+                record.bump = ctx
+                    .bumps
+                    .buyer_record
+                    .ok_or(VRGDAError::BuyerRecordMissing)?;
+            }
+            record.quote_spent = record
+                .quote_spent
+                .checked_add(vrgda.current_price)
+                .ok_or(VRGDAError::MathOverflow)?;
+            record.tokens_bought = record
+                .tokens_bought
+                .checked_add(amount)
+                .ok_or(VRGDAError::MathOverflow)?;
+
+            let now = Clock::get()?.unix_timestamp;
+            if let Some(cooldown) = vrgda.rate_limits.wallet_cooldown_seconds {
+                require!(
+                    now.saturating_sub(record.last_purchase_timestamp) >= cooldown,
+                    VRGDAError::WalletCooldownActive
+                );
+            }
+            record.last_purchase_timestamp = now;
+        } else {
             require!(
-                amount < vrgda.total_supply,
-                VRGDAError::AmountExceedsTotalSupply
+                vrgda.soft_cap.is_none() && vrgda.rate_limits.wallet_cooldown_seconds.is_none(),
+                VRGDAError::BuyerRecordMissing
             );
-            require!(vrgda.auction_ended == false, VRGDAError::AuctionEnded);        
+        }
 
-            // Reduce total supply
-            vrgda.total_supply = vrgda.total_supply.checked_sub(amount).unwrap();
+        let vrgda_seeds = vrgda_signer.seeds();
+        let signer = &[&vrgda_seeds[..]];
 
-            // Special handling for the first purchase
-            let now = Clock::get()?.unix_timestamp;
-            let sold = vrgda.tokens_sold;
-            let schedule = &vrgda.schedule;
+        // Vesting auctions lock the purchase in the recipient's escrow instead of their ATA.
+        let token_destination = if let Some(vesting) = ctx.accounts.vrgda.vesting {
+            let vesting_vault = ctx
+                .accounts
+                .vesting_vault
+                .as_ref()
+                .ok_or(VRGDAError::VestingAccountsMissing)?;
+            let vrgda_key = ctx.accounts.vrgda.key();
+            let escrow = ctx
+                .accounts
+                .vesting_escrow
+                .as_mut()
+                .ok_or(VRGDAError::VestingAccountsMissing)?;
 
-            let time_since_last_buy = if sold == 0 {
-                0
-            } else {
-                now
-                    .checked_sub(vrgda.last_buy_timestamp)
-                    .unwrap_or(0)  
-            };
+            if escrow.vrgda == Pubkey::default() {
+                escrow.vrgda = vrgda_key;
+                escrow.mint = vrgda.mint;
+                escrow.beneficiary = ctx.accounts.recipient.key();
+                escrow.config = vesting;
+                escrow.bump = ctx
+                    .bumps
+                    .vesting_escrow
+                    .ok_or(VRGDAError::VestingAccountsMissing)?;
+            }
+            escrow.deposit(amount, Clock::get()?.unix_timestamp)?;
+            msg!("Deposited {} tokens into vesting escrow", amount);
 
-            msg!("Now: {}", now);
-            msg!("Tokens sold before purchase: {}", sold);
-            msg!("R val: {:?}", schedule);
+            vesting_vault.to_account_info()
+        } else {
+            ctx.accounts.recipient_ata.to_account_info()
+        };
 
-            let price_in_sol =
-                // Use the standard VRGDA pricing for subsequent purchases
-                vrgda.vrgda_price_for_amount(now, sold, amount)?;
+        if ctx.accounts.vrgda.mint_mode == MintMode::PreMint {
+            extensions::transfer_checked(
+                &ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.vrgda_vault.to_account_info(),
+                ctx.accounts.mint.to_account_info(),
+                token_destination,
+                ctx.accounts.vrgda.to_account_info(),
+                ctx.remaining_accounts,
+                tokens_out,
+                ctx.accounts.mint.decimals,
+                signer,
+            )?;
+        } else {
+            // Call the CPI to mint tokens.
+            token_interface::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    anchor_spl::token_interface::MintTo {
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: token_destination,
+                        authority: ctx.accounts.vrgda.to_account_info(),
+                    },
+                    signer,
+                ),
+                amount,
+            )?;
+        }
 
-            // Update tokens sold after price calculation
-            vrgda.tokens_sold = vrgda.tokens_sold.checked_add(amount).unwrap();
+        Ok(PurchaseReceipt {
+            amount,
+            price: vrgda.current_price,
+            cost,
+        })
+    }
 
-            // Scale the price for transfer
-            let mut price_scaled_down = to_actual_mint_amount(&price_in_sol);
-            msg!("Price in SOL: {:?}", price_scaled_down);
+    /// What buying `amount` tokens would cost right now, returned through
+    /// return data like `buy`. Rate limits and the remaining supply are not
+    /// checked.
+    pub fn quote(ctx: Context<Quote>, amount: u64) -> Result<PurchaseReceipt> {
+        require!(amount != 0, VRGDAError::AmountCantBeZero);
+        let vrgda = &ctx.accounts.vrgda;
+        require!(!vrgda.auction_ended, VRGDAError::AuctionEnded);
+        let price = vrgda.sale_price(Clock::get()?.unix_timestamp, amount)?;
+        let cost = extensions::gross_up(&ctx.accounts.wsol_mint.to_account_info(), price)?;
+        Ok(PurchaseReceipt {
+            amount,
+            price,
+            cost,
+        })
+    }
 
-            msg!(
-                "Time since last buy: {} seconds",
-                time_since_last_buy
-            );
+    /// Buys for several recipients in one go. The batch is priced once for its
+    /// summed amount and paid with a single transfer. `remaining_accounts` starts
+    /// with the recipients' token accounts of the sale mint, in `recipient_index`
+    /// order, followed by any transfer-hook accounts.
+    pub fn buy_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BuyBatch<'info>>,
+        purchases: Vec<BatchPurchase>,
+    ) -> Result<()> {
+        require!(!purchases.is_empty(), VRGDAError::AmountCantBeZero);
+        require!(
+            ctx.accounts.vrgda.mint_mode != MintMode::Editions,
+            VRGDAError::MintModeMismatch
+        );
+        // Buyer records and vesting escrows are kept per recipient, which a batch
+        // has no accounts for.
+        require!(
+            !ctx.accounts.vrgda.needs_buyer_accounts(),
+            VRGDAError::PerBuyerAccountsRequired
+        );
 
-            msg!(
-                "Buy window time: {} seconds",
-                vrgda.buy_window_time
+        let recipient_count = purchases
+            .iter()
+            .map(|purchase| purchase.recipient_index as usize + 1)
+            .max()
+            .unwrap_or(0);
+        require!(
+            ctx.remaining_accounts.len() >= recipient_count,
+            VRGDAError::InvalidBatchRecipient
+        );
+        let (recipient_accounts, hook_accounts) = ctx.remaining_accounts.split_at(recipient_count);
+        for account in recipient_accounts {
+            let recipient_ata = InterfaceAccount::<TokenAccount>::try_from(account)?;
+            require_keys_eq!(
+                recipient_ata.mint,
+                ctx.accounts.mint.key(),
+                VRGDAError::InvalidBatchRecipient
             );
+        }
 
-            if time_since_last_buy > vrgda.buy_window_time {
-                price_scaled_down = vrgda.current_price;
-            }
-
-            // Save the updated current_price in state
-            vrgda.current_price = price_scaled_down;
-            vrgda.last_buy_timestamp = now;
+        // Pre-minted tokens leave the vault through transfers, so each one is
+        // grossed up for the sale mint's transfer fee like in `buy`.
+        let mut amount: u64 = 0;
+        let mut deliveries = Vec::with_capacity(purchases.len());
+        for purchase in &purchases {
+            require!(purchase.amount != 0, VRGDAError::AmountCantBeZero);
+            amount = amount
+                .checked_add(purchase.amount)
+                .ok_or(VRGDAError::MathOverflow)?;
+            let tokens_out = if ctx.accounts.vrgda.mint_mode == MintMode::PreMint {
+                extensions::gross_up(&ctx.accounts.mint.to_account_info(), purchase.amount)?
+            } else {
+                purchase.amount
+            };
+            deliveries.push(tokens_out);
         }
+        let tokens_out = deliveries
+            .iter()
+            .try_fold(0u64, |total, out| total.checked_add(*out))
+            .ok_or(VRGDAError::MathOverflow)?;
 
-        let vrgda = &ctx.accounts.vrgda;
-        // Now, create the signer seeds using the (immutable) account data.
-        let mint_key = ctx.accounts.vrgda.mint;
-        let authority_key = ctx.accounts.vrgda.authority;
-        let bump = ctx.accounts.vrgda.bump;
+        book_purchase(&mut ctx.accounts.vrgda, amount, tokens_out)?;
 
-        // Transfer SOL from buyer to their WSOL ATA to fund the purchase
-        anchor_lang::system_program::transfer(
-            CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.buyer.to_account_info(),
-                    to: ctx.accounts.buyer_wsol_ata.to_account_info(),
-                },
-            ),
-            vrgda.current_price,
+        let vrgda = &ctx.accounts.vrgda;
+        let payment_destination = proceeds_destination(
+            vrgda,
+            ctx.accounts.vrgda_sol_ata.to_account_info(),
+            ctx.accounts.proceeds_vault.as_deref(),
         )?;
 
-        // transfer from buyer to vrgda_wallet
-        transfer_checked(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    from: ctx.accounts.buyer_wsol_ata.to_account_info(),
-                    to: ctx.accounts.vrgda_sol_ata.to_account_info(),
-                    authority: ctx.accounts.buyer.to_account_info(),
-                    mint: ctx.accounts.wsol_mint.to_account_info(),
-                },
-            ),
+        collect_payment(
+            Payment {
+                buyer: ctx.accounts.buyer.to_account_info(),
+                buyer_wsol_ata: ctx.accounts.buyer_wsol_ata.to_account_info(),
+                wsol_mint: &ctx.accounts.wsol_mint,
+                destination: payment_destination,
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                hook_accounts,
+            },
             vrgda.current_price,
-            ctx.accounts.wsol_mint.decimals,
         )?;
+        msg!(
+            "Batch of {} purchases for {} recipients",
+            purchases.len(),
+            recipient_count
+        );
 
-        let vrgda_seeds = &[
-            b"vrgda".as_ref(),
-            mint_key.as_ref(),
-            authority_key.as_ref(),
-            &[bump],
-        ];
-        let signer = &[&vrgda_seeds[..]];
+        let delivery = Delivery {
+            vrgda,
+            mint: &ctx.accounts.mint,
+            vrgda_vault: ctx.accounts.vrgda_vault.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            hook_accounts,
+        };
+        for (purchase, tokens_out) in purchases.iter().zip(deliveries) {
+            deliver_tokens(
+                &delivery,
+                recipient_accounts[purchase.recipient_index as usize].clone(),
+                purchase.amount,
+                tokens_out,
+            )?;
+        }
 
-        // Call the CPI to mint tokens.
-        token_interface::transfer_checked(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    from: ctx.accounts.vrgda_vault.to_account_info(),
-                    to: ctx.accounts.buyer_ata.to_account_info(),
-                    authority: ctx.accounts.vrgda.to_account_info(),
-                    mint: ctx.accounts.mint.to_account_info(),
-                },
-            )
-            .with_signer(signer),
+        Ok(())
+    }
+
+    /// Escrows `max_price + tip` quote tokens for a buy of `amount` that anyone
+    /// can execute with `execute_limit_order` once the price is within the limit.
+    pub fn place_limit_order<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PlaceLimitOrder<'info>>,
+        order_id: u64,
+        amount: u64,
+        max_price: u64,
+        tip: u64,
+    ) -> Result<()> {
+        require!(amount != 0, VRGDAError::AmountCantBeZero);
+        let vrgda = &ctx.accounts.vrgda;
+        require!(!vrgda.auction_ended, VRGDAError::AuctionEnded);
+        require!(
+            vrgda.mint_mode != MintMode::Editions,
+            VRGDAError::MintModeMismatch
+        );
+        require!(
+            !vrgda.needs_buyer_accounts(),
+            VRGDAError::PerBuyerAccountsRequired
+        );
+
+        ctx.accounts.limit_order.set_inner(LimitOrder {
+            vrgda: vrgda.key(),
+            owner: ctx.accounts.owner.key(),
+            order_id,
             amount,
-            ctx.accounts.mint.decimals,
+            max_price,
+            tip,
+            bump: ctx.bumps.limit_order,
+        });
+
+        let escrowed = max_price.checked_add(tip).ok_or(VRGDAError::MathOverflow)?;
+        collect_payment(
+            Payment {
+                buyer: ctx.accounts.owner.to_account_info(),
+                buyer_wsol_ata: ctx.accounts.owner_wsol_ata.to_account_info(),
+                wsol_mint: &ctx.accounts.wsol_mint,
+                destination: ctx.accounts.order_escrow.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                hook_accounts: ctx.remaining_accounts,
+            },
+            escrowed,
         )?;
+        msg!(
+            "Limit order for {} tokens at up to {}, tip {}",
+            amount,
+            max_price,
+            tip
+        );
 
         Ok(())
     }
 
-    pub fn close_auction(ctx: Context<CloseAuction>) -> Result<()> {
+    /// Returns an open order's escrow to its owner.
+    pub fn cancel_limit_order<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelLimitOrder<'info>>,
+    ) -> Result<()> {
+        let order = &ctx.accounts.limit_order;
+        let vrgda_key = order.vrgda;
+        let owner_key = order.owner;
+        let order_id = order.order_id.to_le_bytes();
+        let order_seeds = &[
+            b"limit_order".as_ref(),
+            vrgda_key.as_ref(),
+            owner_key.as_ref(),
+            order_id.as_ref(),
+            &[order.bump],
+        ];
+        let signer = &[&order_seeds[..]];
+
+        release_order_escrow(
+            OrderEscrow {
+                order: order.to_account_info(),
+                escrow: &ctx.accounts.order_escrow,
+                wsol_mint: &ctx.accounts.wsol_mint,
+                owner: ctx.accounts.owner.to_account_info(),
+                owner_wsol_ata: ctx.accounts.owner_wsol_ata.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                hook_accounts: ctx.remaining_accounts,
+            },
+            ctx.accounts.order_escrow.amount,
+            signer,
+        )
+    }
+
+    /// Permissionless crank that fills a limit order once buying its amount
+    /// costs no more than the limit. The cranker earns the order's tip and the
+    /// owner gets back whatever the buy did not use.
+    pub fn execute_limit_order<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteLimitOrder<'info>>,
+    ) -> Result<()> {
+        // A wallet cooldown may have been switched on after the order was placed.
         require!(
-            ctx.accounts.vrgda.auction_ended == false,
-            VRGDAError::AuctionEnded
+            !ctx.accounts.vrgda.needs_buyer_accounts(),
+            VRGDAError::PerBuyerAccountsRequired
         );
-        // Close the VRGDA account and transfer any remaining SOL to the authority.
-        {
-            let vrgda = &mut ctx.accounts.vrgda;
-            vrgda.auction_ended = true;
-        }
-
-        let authority = &ctx.accounts.authority;
+        let amount = ctx.accounts.limit_order.amount;
+        let tokens_out = if ctx.accounts.vrgda.mint_mode == MintMode::PreMint {
+            extensions::gross_up(&ctx.accounts.mint.to_account_info(), amount)?
+        } else {
+            amount
+        };
+        book_purchase(&mut ctx.accounts.vrgda, amount, tokens_out)?;
 
+        // The order pays the quote mint's transfer fee, on the payment and on
+        // the tip, out of its limit, so the cranker receives the full tip.
         let vrgda = &ctx.accounts.vrgda;
-        // Transfer any remaining SOL from the VRGDA vault to the authority.
-        let remaining_sol = ctx.accounts.vrgda_sol_ata.amount;
-        if remaining_sol > 0 {
-            token_interface::transfer_checked(
-                CpiContext::new(
-                    ctx.accounts.token_program.to_account_info(),
-                    TransferChecked {
-                        from: ctx.accounts.vrgda_sol_ata.to_account_info(),
-                        mint: ctx.accounts.wsol_mint.to_account_info(),
-                        to: ctx.accounts.authority.to_account_info(),
-                        authority: ctx.accounts.vrgda.to_account_info(),
-                    },
-                ),
-                remaining_sol,
+        let order = &ctx.accounts.limit_order;
+        let payment = extensions::gross_up(
+            &ctx.accounts.wsol_mint.to_account_info(),
+            vrgda.current_price,
+        )?;
+        let tip = extensions::gross_up(&ctx.accounts.wsol_mint.to_account_info(), order.tip)?;
+        let cost = tip
+            .checked_sub(order.tip)
+            .and_then(|tip_fee| payment.checked_add(tip_fee))
+            .ok_or(VRGDAError::MathOverflow)?;
+        require!(cost <= order.max_price, VRGDAError::LimitPriceNotReached);
+        msg!(
+            "Executing limit order at {} against limit {}",
+            payment,
+            order.max_price
+        );
+
+        let vrgda_key = order.vrgda;
+        let owner_key = order.owner;
+        let order_id = order.order_id.to_le_bytes();
+        let order_seeds = &[
+            b"limit_order".as_ref(),
+            vrgda_key.as_ref(),
+            owner_key.as_ref(),
+            order_id.as_ref(),
+            &[order.bump],
+        ];
+        let order_signer = &[&order_seeds[..]];
+
+        let payment_destination = proceeds_destination(
+            vrgda,
+            ctx.accounts.vrgda_sol_ata.to_account_info(),
+            ctx.accounts.proceeds_vault.as_deref(),
+        )?;
+        extensions::transfer_checked(
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.order_escrow.to_account_info(),
+            ctx.accounts.wsol_mint.to_account_info(),
+            payment_destination,
+            order.to_account_info(),
+            ctx.remaining_accounts,
+            payment,
+            ctx.accounts.wsol_mint.decimals,
+            order_signer,
+        )?;
+        if tip > 0 {
+            extensions::transfer_checked(
+                &ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.order_escrow.to_account_info(),
+                ctx.accounts.wsol_mint.to_account_info(),
+                ctx.accounts.cranker_wsol_ata.to_account_info(),
+                order.to_account_info(),
+                ctx.remaining_accounts,
+                tip,
                 ctx.accounts.wsol_mint.decimals,
+                order_signer,
             )?;
         }
-        // Close the VRGDA account and transfer any remaining tokens to the authority.
-        vrgda.close(authority.to_account_info())?;
+
+        let leftover = ctx
+            .accounts
+            .order_escrow
+            .amount
+            .checked_sub(payment)
+            .and_then(|rest| rest.checked_sub(tip))
+            .ok_or(VRGDAError::MathOverflow)?;
+        release_order_escrow(
+            OrderEscrow {
+                order: order.to_account_info(),
+                escrow: &ctx.accounts.order_escrow,
+                wsol_mint: &ctx.accounts.wsol_mint,
+                owner: ctx.accounts.owner.to_account_info(),
+                owner_wsol_ata: ctx.accounts.owner_wsol_ata.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                hook_accounts: ctx.remaining_accounts,
+            },
+            leftover,
+            order_signer,
+        )?;
+
+        deliver_tokens(
+            &Delivery {
+                vrgda,
+                mint: &ctx.accounts.mint,
+                vrgda_vault: ctx.accounts.vrgda_vault.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                hook_accounts: ctx.remaining_accounts,
+            },
+            ctx.accounts.owner_ata.to_account_info(),
+            amount,
+            tokens_out,
+        )?;
 
         Ok(())
     }
 
-    // pub fn sell(ctx: Context<Sell>, amount: u64) -> Result<()> {
-    //     // Make sure the seller is not trying to sell 0 tokens.
-    //     require!(amount != 0, VRGDAError::AmountCantBeZero);
-    //     // It makes sense to require that the amount to sell is no more than what has been sold
-    //     // (i.e. the VRGDA’s tokens_sold value), so that we can “reverse” part of the sale.
-    //     require!(amount <= ctx.accounts.vrgda.tokens_sold, VRGDAError::AmountExceedsTotalSupply);
+    /// Escrows `slices * max_price_per_slice` quote tokens for a recurring buy
+    /// of `amount_per_slice` every `interval_seconds`. The first slice is due
+    /// right away.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_dca_order<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateDcaOrder<'info>>,
+        order_id: u64,
+        amount_per_slice: u64,
+        max_price_per_slice: u64,
+        interval_seconds: i64,
+        slices: u64,
+    ) -> Result<()> {
+        require!(
+            amount_per_slice != 0 && interval_seconds > 0 && slices != 0,
+            VRGDAError::InvalidDcaConfig
+        );
+        let vrgda = &ctx.accounts.vrgda;
+        require!(!vrgda.auction_ended, VRGDAError::AuctionEnded);
+        require!(
+            vrgda.mint_mode != MintMode::Editions,
+            VRGDAError::MintModeMismatch
+        );
+        require!(
+            !vrgda.needs_buyer_accounts(),
+            VRGDAError::PerBuyerAccountsRequired
+        );
 
-    //     // Get the current time (in seconds) as a fixed-point i128.
-    //     let now = Clock::get()?.unix_timestamp;
-    //     let sold = ctx.accounts.vrgda.tokens_sold;
-    //     let schedule = &ctx.accounts.vrgda.schedule;
+        ctx.accounts.dca_order.set_inner(DcaOrder {
+            vrgda: vrgda.key(),
+            owner: ctx.accounts.owner.key(),
+            order_id,
+            amount_per_slice,
+            max_price_per_slice,
+            interval_seconds,
+            slices_remaining: slices,
+            next_slice_timestamp: Clock::get()?.unix_timestamp,
+            bump: ctx.bumps.dca_order,
+        });
 
-    //     // Compute the current price per token according to the VRGDA pricing formula.
-    //     let price_in_sol = ctx.accounts.vrgda.vrgda_price(now, sold)?;
-    //     msg!("Current price per token in SOL: {:?}", price_in_sol);
+        let escrowed = max_price_per_slice
+            .checked_mul(slices)
+            .ok_or(VRGDAError::MathOverflow)?;
+        collect_payment(
+            Payment {
+                buyer: ctx.accounts.owner.to_account_info(),
+                buyer_wsol_ata: ctx.accounts.owner_wsol_ata.to_account_info(),
+                wsol_mint: &ctx.accounts.wsol_mint,
+                destination: ctx.accounts.order_escrow.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                hook_accounts: ctx.remaining_accounts,
+            },
+            escrowed,
+        )?;
+        msg!(
+            "DCA order for {} slices of {} tokens every {} seconds",
+            slices,
+            amount_per_slice,
+            interval_seconds
+        );
 
-    //     // Compute the total refund as (price per token) * (amount being sold)
-    //     let refund = calculate_refund(amount, &ctx.accounts.wsol_mint, price_in_sol);
+        Ok(())
+    }
 
-    //     msg!("Total refund amount in SOL: {}", refund);
+    /// Returns what is left of a DCA order's escrow to its owner.
+    pub fn cancel_dca_order<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelDcaOrder<'info>>,
+    ) -> Result<()> {
+        let order = &ctx.accounts.dca_order;
+        let vrgda_key = order.vrgda;
+        let owner_key = order.owner;
+        let order_id = order.order_id.to_le_bytes();
+        let order_seeds = &[
+            b"dca".as_ref(),
+            vrgda_key.as_ref(),
+            owner_key.as_ref(),
+            order_id.as_ref(),
+            &[order.bump],
+        ];
+        let signer = &[&order_seeds[..]];
 
-    //       // Prepare the VRGDA PDA signer seeds – they must match the ones used when minting.
-    //     let vrgda_seeds = &[
-    //         b"vrgda".as_ref(),
-    //         ctx.accounts.vrgda.mint.as_ref(),
-    //         ctx.accounts.authority.key.as_ref(),
-    //         &[ctx.accounts.vrgda.bump],
-    //     ];
-    //     let signer = &[&vrgda_seeds[..]];
-    //     // Burn the tokens from the seller’s token account.
-    //     // This call burns `amount` tokens from seller_ata.
-    //     token_interface::burn(
-    //         CpiContext::new(
-    //             ctx.accounts.token_program.to_account_info(),
-    //             token_interface::Burn {
+        release_order_escrow(
+            OrderEscrow {
+                order: order.to_account_info(),
+                escrow: &ctx.accounts.order_escrow,
+                wsol_mint: &ctx.accounts.wsol_mint,
+                owner: ctx.accounts.owner.to_account_info(),
+                owner_wsol_ata: ctx.accounts.owner_wsol_ata.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                hook_accounts: ctx.remaining_accounts,
+            },
+            ctx.accounts.order_escrow.amount,
+            signer,
+        )
+    }
+
+    /// Permissionless crank that buys the next due slice of a DCA order, priced
+    /// like a regular `buy`. Fails while the slice would cost more than the
+    /// order's per-slice limit, so it can simply be retried later. The next
+    /// slice is due one interval after this one ran.
+    pub fn execute_dca<'info>(ctx: Context<'_, '_, 'info, 'info, ExecuteDca<'info>>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= ctx.accounts.dca_order.next_slice_timestamp,
+            VRGDAError::DcaSliceNotDue
+        );
+        // A wallet cooldown may have been switched on after the order was placed.
+        require!(
+            !ctx.accounts.vrgda.needs_buyer_accounts(),
+            VRGDAError::PerBuyerAccountsRequired
+        );
+
+        let amount = ctx.accounts.dca_order.amount_per_slice;
+        let tokens_out = if ctx.accounts.vrgda.mint_mode == MintMode::PreMint {
+            extensions::gross_up(&ctx.accounts.mint.to_account_info(), amount)?
+        } else {
+            amount
+        };
+        book_purchase(&mut ctx.accounts.vrgda, amount, tokens_out)?;
+
+        // The order pays the quote mint's transfer fee out of its limit.
+        let vrgda = &ctx.accounts.vrgda;
+        let payment = extensions::gross_up(
+            &ctx.accounts.wsol_mint.to_account_info(),
+            vrgda.current_price,
+        )?;
+        require!(
+            payment <= ctx.accounts.dca_order.max_price_per_slice,
+            VRGDAError::LimitPriceNotReached
+        );
+
+        let order = &mut ctx.accounts.dca_order;
+        order.slices_remaining = order
+            .slices_remaining
+            .checked_sub(1)
+            .ok_or(VRGDAError::MathOverflow)?;
+        order.next_slice_timestamp = now
+            .checked_add(order.interval_seconds)
+            .ok_or(VRGDAError::MathOverflow)?;
+        msg!(
+            "DCA slice at {}, {} slices remaining",
+            payment,
+            order.slices_remaining
+        );
+
+        let vrgda_key = order.vrgda;
+        let owner_key = order.owner;
+        let order_id = order.order_id.to_le_bytes();
+        let order_seeds = &[
+            b"dca".as_ref(),
+            vrgda_key.as_ref(),
+            owner_key.as_ref(),
+            order_id.as_ref(),
+            &[order.bump],
+        ];
+        let order_signer = &[&order_seeds[..]];
+
+        let payment_destination = proceeds_destination(
+            vrgda,
+            ctx.accounts.vrgda_sol_ata.to_account_info(),
+            ctx.accounts.proceeds_vault.as_deref(),
+        )?;
+        extensions::transfer_checked(
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.order_escrow.to_account_info(),
+            ctx.accounts.wsol_mint.to_account_info(),
+            payment_destination,
+            order.to_account_info(),
+            ctx.remaining_accounts,
+            payment,
+            ctx.accounts.wsol_mint.decimals,
+            order_signer,
+        )?;
+
+        deliver_tokens(
+            &Delivery {
+                vrgda,
+                mint: &ctx.accounts.mint,
+                vrgda_vault: ctx.accounts.vrgda_vault.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                hook_accounts: ctx.remaining_accounts,
+            },
+            ctx.accounts.owner_ata.to_account_info(),
+            amount,
+            tokens_out,
+        )?;
+
+        // After the last slice the unspent escrow goes back and the order closes.
+        if order.slices_remaining == 0 {
+            let leftover = ctx
+                .accounts
+                .order_escrow
+                .amount
+                .checked_sub(payment)
+                .ok_or(VRGDAError::MathOverflow)?;
+            release_order_escrow(
+                OrderEscrow {
+                    order: order.to_account_info(),
+                    escrow: &ctx.accounts.order_escrow,
+                    wsol_mint: &ctx.accounts.wsol_mint,
+                    owner: ctx.accounts.owner.to_account_info(),
+                    owner_wsol_ata: ctx.accounts.owner_wsol_ata.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    hook_accounts: ctx.remaining_accounts,
+                },
+                leftover,
+                order_signer,
+            )?;
+            order.close(ctx.accounts.owner.to_account_info())?;
+        }
+
+        Ok(())
+    }
+
+    /// Buys for a buyer who signed `intent` off-chain, so that a relayer can
+    /// submit the transaction and pay its fees. The instruction right before
+    /// this one has to be the ed25519 precompile verifying the buyer's signature
+    /// over `intent.message()`. The payment is pulled from the buyer's quote
+    /// account, which must have approved the auction PDA as delegate.
+    pub fn buy_with_intent<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BuyWithIntent<'info>>,
+        intent: PurchaseIntent,
+    ) -> Result<()> {
+        require!(intent.amount != 0, VRGDAError::AmountCantBeZero);
+        require_keys_eq!(
+            intent.vrgda,
+            ctx.accounts.vrgda.key(),
+            VRGDAError::IntentMismatch
+        );
+        require!(
+            Clock::get()?.unix_timestamp <= intent.expiry,
+            VRGDAError::IntentExpired
+        );
+        require!(
+            ctx.accounts.vrgda.mint_mode != MintMode::Editions,
+            VRGDAError::MintModeMismatch
+        );
+        require!(
+            !ctx.accounts.vrgda.needs_buyer_accounts(),
+            VRGDAError::PerBuyerAccountsRequired
+        );
+        intent::verify_ed25519_signature(
+            &ctx.accounts.instructions.to_account_info(),
+            &ctx.accounts.buyer.key(),
+            &intent.message()?,
+        )?;
+        ctx.accounts.intent_nonce.bump = ctx.bumps.intent_nonce;
+
+        let tokens_out = if ctx.accounts.vrgda.mint_mode == MintMode::PreMint {
+            extensions::gross_up(&ctx.accounts.mint.to_account_info(), intent.amount)?
+        } else {
+            intent.amount
+        };
+        book_purchase(&mut ctx.accounts.vrgda, intent.amount, tokens_out)?;
+
+        // The buyer pays the quote mint's transfer fee out of the intent's limit.
+        let vrgda = &ctx.accounts.vrgda;
+        let payment = extensions::gross_up(
+            &ctx.accounts.wsol_mint.to_account_info(),
+            vrgda.current_price,
+        )?;
+        require!(payment <= intent.max_cost, VRGDAError::IntentCostExceeded);
+        msg!(
+            "Intent {} from {} paid {}",
+            intent.nonce,
+            ctx.accounts.buyer.key(),
+            payment
+        );
+
+        let vrgda_signer = vrgda.signer();
+        let vrgda_seeds = vrgda_signer.seeds();
+        let signer = &[&vrgda_seeds[..]];
+
+        let payment_destination = proceeds_destination(
+            vrgda,
+            ctx.accounts.vrgda_sol_ata.to_account_info(),
+            ctx.accounts.proceeds_vault.as_deref(),
+        )?;
+        extensions::transfer_checked(
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.buyer_wsol_ata.to_account_info(),
+            ctx.accounts.wsol_mint.to_account_info(),
+            payment_destination,
+            vrgda.to_account_info(),
+            ctx.remaining_accounts,
+            payment,
+            ctx.accounts.wsol_mint.decimals,
+            signer,
+        )?;
+
+        deliver_tokens(
+            &Delivery {
+                vrgda,
+                mint: &ctx.accounts.mint,
+                vrgda_vault: ctx.accounts.vrgda_vault.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                hook_accounts: ctx.remaining_accounts,
+            },
+            ctx.accounts.buyer_ata.to_account_info(),
+            intent.amount,
+            tokens_out,
+        )
+    }
+
+    /// Hides a purchase behind `commitment`, `PurchaseCommit::hash` of the
+    /// amount, max cost and a secret salt, and escrows `deposit` quote tokens to
+    /// pay for it. The deposit has to cover the max cost.
+    pub fn commit<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Commit<'info>>,
+        commitment: [u8; 32],
+        deposit: u64,
+    ) -> Result<()> {
+        let vrgda = &ctx.accounts.vrgda;
+        let config = vrgda
+            .commit_reveal
+            .ok_or(VRGDAError::CommitRevealDisabled)?;
+        require!(!vrgda.auction_ended, VRGDAError::AuctionEnded);
+
+        // The slots are fixed here so later config changes leave the commit alone.
+        let slot = Clock::get()?.slot;
+        ctx.accounts.purchase_commit.set_inner(PurchaseCommit {
+            vrgda: vrgda.key(),
+            buyer: ctx.accounts.buyer.key(),
+            commitment,
+            reveal_slot: slot
+                .checked_add(config.min_delay_slots)
+                .ok_or(VRGDAError::MathOverflow)?,
+            expiry_slot: slot
+                .checked_add(config.reveal_timeout_slots)
+                .ok_or(VRGDAError::MathOverflow)?,
+            bump: ctx.bumps.purchase_commit,
+        });
+
+        collect_payment(
+            Payment {
+                buyer: ctx.accounts.buyer.to_account_info(),
+                buyer_wsol_ata: ctx.accounts.buyer_wsol_ata.to_account_info(),
+                wsol_mint: &ctx.accounts.wsol_mint,
+                destination: ctx.accounts.commit_escrow.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                hook_accounts: ctx.remaining_accounts,
+            },
+            deposit,
+        )?;
+        Ok(())
+    }
+
+    /// Opens a commit and buys `amount` at the price of the reveal slot, as long
+    /// as that stays within `max_cost`. The unused deposit goes back to the buyer.
+    pub fn reveal<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Reveal<'info>>,
+        amount: u64,
+        max_cost: u64,
+        salt: [u8; 32],
+    ) -> Result<()> {
+        require!(amount != 0, VRGDAError::AmountCantBeZero);
+        let commit = &ctx.accounts.purchase_commit;
+        require!(
+            commit.commitment == PurchaseCommit::hash(amount, max_cost, &salt),
+            VRGDAError::CommitMismatch
+        );
+        let slot = Clock::get()?.slot;
+        require!(slot >= commit.reveal_slot, VRGDAError::RevealTooEarly);
+        require!(slot < commit.expiry_slot, VRGDAError::CommitExpired);
+        require!(
+            ctx.accounts.vrgda.mint_mode != MintMode::Editions,
+            VRGDAError::MintModeMismatch
+        );
+        require!(
+            !ctx.accounts.vrgda.needs_buyer_accounts(),
+            VRGDAError::PerBuyerAccountsRequired
+        );
+
+        let tokens_out = if ctx.accounts.vrgda.mint_mode == MintMode::PreMint {
+            extensions::gross_up(&ctx.accounts.mint.to_account_info(), amount)?
+        } else {
+            amount
+        };
+        record_sale(&mut ctx.accounts.vrgda, amount, tokens_out)?;
+
+        // The buyer pays the quote mint's transfer fee out of the committed max.
+        let vrgda = &ctx.accounts.vrgda;
+        let payment = extensions::gross_up(
+            &ctx.accounts.wsol_mint.to_account_info(),
+            vrgda.current_price,
+        )?;
+        require!(payment <= max_cost, VRGDAError::CommitCostExceeded);
+        msg!("Revealed {} tokens for {}", amount, payment);
+
+        let commit = &ctx.accounts.purchase_commit;
+        let vrgda_key = commit.vrgda;
+        let buyer_key = commit.buyer;
+        let commit_seeds = &[
+            b"commit".as_ref(),
+            vrgda_key.as_ref(),
+            buyer_key.as_ref(),
+            &[commit.bump],
+        ];
+        let commit_signer = &[&commit_seeds[..]];
+
+        let payment_destination = proceeds_destination(
+            vrgda,
+            ctx.accounts.vrgda_sol_ata.to_account_info(),
+            ctx.accounts.proceeds_vault.as_deref(),
+        )?;
+        extensions::transfer_checked(
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.commit_escrow.to_account_info(),
+            ctx.accounts.wsol_mint.to_account_info(),
+            payment_destination,
+            commit.to_account_info(),
+            ctx.remaining_accounts,
+            payment,
+            ctx.accounts.wsol_mint.decimals,
+            commit_signer,
+        )?;
+
+        let leftover = ctx
+            .accounts
+            .commit_escrow
+            .amount
+            .checked_sub(payment)
+            .ok_or(VRGDAError::MathOverflow)?;
+        release_order_escrow(
+            OrderEscrow {
+                order: commit.to_account_info(),
+                escrow: &ctx.accounts.commit_escrow,
+                wsol_mint: &ctx.accounts.wsol_mint,
+                owner: ctx.accounts.buyer.to_account_info(),
+                owner_wsol_ata: ctx.accounts.buyer_wsol_ata.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                hook_accounts: ctx.remaining_accounts,
+            },
+            leftover,
+            commit_signer,
+        )?;
+
+        deliver_tokens(
+            &Delivery {
+                vrgda,
+                mint: &ctx.accounts.mint,
+                vrgda_vault: ctx.accounts.vrgda_vault.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                hook_accounts: ctx.remaining_accounts,
+            },
+            ctx.accounts.buyer_ata.to_account_info(),
+            amount,
+            tokens_out,
+        )
+    }
+
+    /// Returns the deposit of a commit that was not revealed in time.
+    pub fn reclaim_commit<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReclaimCommit<'info>>,
+    ) -> Result<()> {
+        let commit = &ctx.accounts.purchase_commit;
+        require!(
+            Clock::get()?.slot >= commit.expiry_slot,
+            VRGDAError::CommitNotExpired
+        );
+
+        let vrgda_key = commit.vrgda;
+        let buyer_key = commit.buyer;
+        let commit_seeds = &[
+            b"commit".as_ref(),
+            vrgda_key.as_ref(),
+            buyer_key.as_ref(),
+            &[commit.bump],
+        ];
+        release_order_escrow(
+            OrderEscrow {
+                order: commit.to_account_info(),
+                escrow: &ctx.accounts.commit_escrow,
+                wsol_mint: &ctx.accounts.wsol_mint,
+                owner: ctx.accounts.buyer.to_account_info(),
+                owner_wsol_ata: ctx.accounts.buyer_wsol_ata.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                hook_accounts: ctx.remaining_accounts,
+            },
+            ctx.accounts.commit_escrow.amount,
+            &[&commit_seeds[..]],
+        )
+    }
+
+    /// Sells the next NFT of an edition drop. One item is priced like one whole
+    /// token of a fungible sale, with `tokens_sold` counting editions.
+    pub fn buy_edition<'info>(ctx: Context<'_, '_, 'info, 'info, BuyEdition<'info>>) -> Result<()> {
+        let (edition, number) = {
+            let vrgda = &mut ctx.accounts.vrgda;
+            require!(
+                vrgda.mint_mode == MintMode::Editions,
+                VRGDAError::MintModeMismatch
+            );
+            let edition = vrgda.edition.clone().ok_or(VRGDAError::MintModeMismatch)?;
+            book_purchase(vrgda, 1, 1)?;
+            msg!(
+                "Edition #{} price: {}",
+                vrgda.tokens_sold,
+                vrgda.current_price
+            );
+
+            (edition, vrgda.tokens_sold)
+        };
+
+        let vrgda = &ctx.accounts.vrgda;
+        let payment_destination = proceeds_destination(
+            vrgda,
+            ctx.accounts.vrgda_sol_ata.to_account_info(),
+            ctx.accounts.proceeds_vault.as_deref(),
+        )?;
+
+        collect_payment(
+            Payment {
+                buyer: ctx.accounts.buyer.to_account_info(),
+                buyer_wsol_ata: ctx.accounts.buyer_wsol_ata.to_account_info(),
+                wsol_mint: &ctx.accounts.wsol_mint,
+                destination: payment_destination,
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                hook_accounts: ctx.remaining_accounts,
+            },
+            vrgda.current_price,
+        )?;
+
+        let vrgda_signer = vrgda.signer();
+        let vrgda_seeds = vrgda_signer.seeds();
+        let signer = &[&vrgda_seeds[..]];
+
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.edition_token_program.to_account_info(),
+                anchor_spl::token_interface::MintTo {
+                    mint: ctx.accounts.edition_mint.to_account_info(),
+                    to: ctx.accounts.recipient_edition_ata.to_account_info(),
+                    authority: vrgda.to_account_info(),
+                },
+                signer,
+            ),
+            1,
+        )?;
+
+        create_metadata_accounts_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.metadata_program.to_account_info(),
+                CreateMetadataAccountsV3 {
+                    metadata: ctx.accounts.edition_metadata.to_account_info(),
+                    mint: ctx.accounts.edition_mint.to_account_info(),
+                    mint_authority: vrgda.to_account_info(),
+                    update_authority: vrgda.to_account_info(),
+                    payer: ctx.accounts.buyer.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                signer,
+            ),
+            DataV2 {
+                name: edition.edition_name(number),
+                symbol: edition.symbol.clone(),
+                uri: edition.edition_uri(number),
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: edition.collection.map(|key| Collection {
+                    verified: false,
+                    key,
+                }),
+                uses: None,
+            },
+            true,
+            true,
+            None,
+        )?;
+
+        // A zero max supply makes the NFT a true 1-of-1 and hands both mint and
+        // freeze authority to the master edition.
+        create_master_edition_v3(
+            CpiContext::new_with_signer(
+                ctx.accounts.metadata_program.to_account_info(),
+                CreateMasterEditionV3 {
+                    edition: ctx.accounts.master_edition.to_account_info(),
+                    mint: ctx.accounts.edition_mint.to_account_info(),
+                    update_authority: vrgda.to_account_info(),
+                    mint_authority: vrgda.to_account_info(),
+                    payer: ctx.accounts.buyer.to_account_info(),
+                    metadata: ctx.accounts.edition_metadata.to_account_info(),
+                    token_program: ctx.accounts.edition_token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+                signer,
+            ),
+            Some(0),
+        )?;
+
+        if let Some(collection) = edition.collection {
+            let collection_mint = ctx
+                .accounts
+                .collection_mint
+                .as_ref()
+                .ok_or(VRGDAError::CollectionAccountsMissing)?;
+            let collection_metadata = ctx
+                .accounts
+                .collection_metadata
+                .as_ref()
+                .ok_or(VRGDAError::CollectionAccountsMissing)?;
+            let collection_master_edition = ctx
+                .accounts
+                .collection_master_edition
+                .as_ref()
+                .ok_or(VRGDAError::CollectionAccountsMissing)?;
+            require_keys_eq!(
+                collection_mint.key(),
+                collection,
+                VRGDAError::CollectionAccountsMissing
+            );
+
+            let record = ctx
+                .accounts
+                .collection_authority_record
+                .as_ref()
+                .map(|record| record.to_account_info());
+            verify_sized_collection_item(
+                CpiContext::new_with_signer(
+                    ctx.accounts.metadata_program.to_account_info(),
+                    VerifySizedCollectionItem {
+                        payer: ctx.accounts.buyer.to_account_info(),
+                        metadata: ctx.accounts.edition_metadata.to_account_info(),
+                        collection_authority: vrgda.to_account_info(),
+                        collection_mint: collection_mint.to_account_info(),
+                        collection_metadata: collection_metadata.to_account_info(),
+                        collection_master_edition: collection_master_edition.to_account_info(),
+                    },
+                    signer,
+                )
+                .with_remaining_accounts(record.iter().cloned().collect()),
+                record.as_ref().map(|record| record.key()),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Changes the token's name, symbol or uri, leaving `None` fields as they are.
+    /// With `lock` set the metadata can never be changed again.
+    pub fn update_metadata(
+        ctx: Context<UpdateMetadata>,
+        name: Option<String>,
+        symbol: Option<String>,
+        uri: Option<String>,
+        lock: bool,
+    ) -> Result<()> {
+        require!(
+            !ctx.accounts.vrgda.metadata_locked,
+            VRGDAError::MetadataLocked
+        );
+
+        match ctx.accounts.vrgda.metadata_mode {
+            MetadataMode::Metaplex => {
+                let metadata = ctx
+                    .accounts
+                    .metadata
+                    .as_ref()
+                    .ok_or(VRGDAError::MetadataAccountsMissing)?;
+                let metadata_program = ctx
+                    .accounts
+                    .metadata_program
+                    .as_ref()
+                    .ok_or(VRGDAError::MetadataAccountsMissing)?;
+
+                let current = mpl_token_metadata::accounts::Metadata::safe_deserialize(
+                    &metadata.try_borrow_data()?,
+                )?;
+                require_keys_eq!(
+                    current.mint,
+                    ctx.accounts.mint.key(),
+                    VRGDAError::AddressesDontMatch
+                );
+
+                // Metaplex pads stored strings with NULs up to their max length.
+                let token_data = DataV2 {
+                    name: name.unwrap_or_else(|| current.name.trim_end_matches('\0').to_string()),
+                    symbol: symbol
+                        .unwrap_or_else(|| current.symbol.trim_end_matches('\0').to_string()),
+                    uri: uri.unwrap_or_else(|| current.uri.trim_end_matches('\0').to_string()),
+                    seller_fee_basis_points: current.seller_fee_basis_points,
+                    creators: current.creators,
+                    collection: current.collection,
+                    uses: current.uses,
+                };
+
+                update_metadata_accounts_v2(
+                    CpiContext::new(
+                        metadata_program.to_account_info(),
+                        UpdateMetadataAccountsV2 {
+                            metadata: metadata.to_account_info(),
+                            update_authority: ctx.accounts.authority.to_account_info(),
+                        },
+                    ),
+                    None,
+                    Some(token_data),
+                    None,
+                    lock.then_some(false),
+                )?;
+            }
+            MetadataMode::Token2022 => {
+                let mint_info = ctx.accounts.mint.to_account_info();
+                let space = extensions::updated_token_metadata_len(
+                    &mint_info,
+                    name.as_deref(),
+                    symbol.as_deref(),
+                    uri.as_deref(),
+                )?;
+                extensions::fund_realloc(
+                    ctx.accounts.system_program.to_account_info(),
+                    ctx.accounts.authority.to_account_info(),
+                    mint_info.clone(),
+                    space,
+                )?;
+
+                for (field, value) in [
+                    (Field::Name, name),
+                    (Field::Symbol, symbol),
+                    (Field::Uri, uri),
+                ] {
+                    if let Some(value) = value {
+                        token_interface::token_metadata_update_field(
+                            CpiContext::new(
+                                ctx.accounts.token_program.to_account_info(),
+                                token_interface::TokenMetadataUpdateField {
+                                    program_id: ctx.accounts.token_program.to_account_info(),
+                                    metadata: mint_info.clone(),
+                                    update_authority: ctx.accounts.authority.to_account_info(),
+                                },
+                            ),
+                            field,
+                            value,
+                        )?;
+                    }
+                }
+
+                // Clearing the update authority freezes the metadata on the mint.
+                if lock {
+                    token_interface::token_metadata_update_authority(
+                        CpiContext::new(
+                            ctx.accounts.token_program.to_account_info(),
+                            token_interface::TokenMetadataUpdateAuthority {
+                                program_id: ctx.accounts.token_program.to_account_info(),
+                                metadata: mint_info,
+                                current_authority: ctx.accounts.authority.to_account_info(),
+                                new_authority: ctx.accounts.authority.to_account_info(),
+                            },
+                        ),
+                        OptionalNonZeroPubkey::default(),
+                    )?;
+                }
+            }
+        }
+
+        if lock {
+            ctx.accounts.vrgda.metadata_locked = true;
+            msg!("Metadata locked");
+        }
+
+        Ok(())
+    }
+
+    /// Permissionless crank that pays every payee their share of the proceeds
+    /// vault. `remaining_accounts` must hold one quote token account per payee,
+    /// in the same order as `vrgda.payees`, or the quote token account of
+    /// `vrgda.payout` when the auction has no payees. Transfer hook accounts for the
+    /// quote mint follow the payee accounts.
+    pub fn distribute<'info>(ctx: Context<'_, '_, 'info, 'info, Distribute<'info>>) -> Result<()> {
+        let vrgda = &ctx.accounts.vrgda;
+        require!(vrgda.soft_cap_met(), VRGDAError::SoftCapNotMet);
+
+        // Auctions without payees pay everything escrowed to `payout`.
+        let wallets: Vec<Pubkey> = if vrgda.payees.is_empty() {
+            vec![vrgda.payout]
+        } else {
+            vrgda.payees.iter().map(|payee| payee.wallet).collect()
+        };
+        require!(
+            ctx.remaining_accounts.len() >= wallets.len(),
+            VRGDAError::PayeeAccountMismatch
+        );
+        let (payee_accounts, hook_accounts) = ctx.remaining_accounts.split_at(wallets.len());
+
+        let total = ctx.accounts.proceeds_vault.amount;
+        let parts = vrgda.split_proceeds(total)?;
+        msg!("Distributing {} across {} payees", total, parts.len());
+
+        let vrgda_signer = vrgda.signer();
+        let vrgda_seeds = vrgda_signer.seeds();
+        let signer = &[&vrgda_seeds[..]];
+
+        for ((wallet, part), payee_account) in wallets.iter().zip(parts).zip(payee_accounts.iter())
+        {
+            let payee_ata = InterfaceAccount::<TokenAccount>::try_from(payee_account)?;
+            require_keys_eq!(payee_ata.owner, *wallet, VRGDAError::PayeeAccountMismatch);
+            require_keys_eq!(
+                payee_ata.mint,
+                ctx.accounts.wsol_mint.key(),
+                VRGDAError::PayeeAccountMismatch
+            );
+            if part == 0 {
+                continue;
+            }
+
+            extensions::transfer_checked(
+                &ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.proceeds_vault.to_account_info(),
+                ctx.accounts.wsol_mint.to_account_info(),
+                payee_account.clone(),
+                vrgda.to_account_info(),
+                hook_accounts,
+                part,
+                ctx.accounts.wsol_mint.decimals,
+                signer,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns a buyer's recorded spend once the soft cap deadline passed without
+    /// meeting the cap, and burns the tokens they received. Any quote mint
+    /// transfer fee on the way back is borne by the buyer.
+    pub fn refund<'info>(ctx: Context<'_, '_, 'info, 'info, Refund<'info>>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            ctx.accounts.vrgda.soft_cap_failed(now),
+            VRGDAError::SoftCapNotMet
+        );
+
+        let record = &ctx.accounts.buyer_record;
+        let refund_amount = record.quote_spent;
+        let mut to_burn = record.tokens_bought;
+        msg!("Refunding {} and burning {} tokens", refund_amount, to_burn);
+
+        let vrgda_signer = ctx.accounts.vrgda.signer();
+        let vrgda_seeds = vrgda_signer.seeds();
+        let signer = &[&vrgda_seeds[..]];
+
+        extensions::transfer_checked(
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.proceeds_vault.to_account_info(),
+            ctx.accounts.wsol_mint.to_account_info(),
+            ctx.accounts.buyer_wsol_ata.to_account_info(),
+            ctx.accounts.vrgda.to_account_info(),
+            ctx.remaining_accounts,
+            refund_amount,
+            ctx.accounts.wsol_mint.decimals,
+            signer,
+        )?;
+
+        // Tokens still locked in a vesting escrow are burned from there first.
+        if let (Some(escrow), Some(vesting_vault)) = (
+            ctx.accounts.vesting_escrow.as_mut(),
+            ctx.accounts.vesting_vault.as_ref(),
+        ) {
+            let locked = escrow
+                .total_amount
+                .checked_sub(escrow.claimed_amount)
+                .ok_or(VRGDAError::MathOverflow)?
+                .min(to_burn);
+            if locked > 0 {
+                let vrgda_key = escrow.vrgda;
+                let beneficiary_key = escrow.beneficiary;
+                let escrow_seeds = &[
+                    b"vesting".as_ref(),
+                    vrgda_key.as_ref(),
+                    beneficiary_key.as_ref(),
+                    &[escrow.bump],
+                ];
+                token_interface::burn(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        token_interface::Burn {
+                            mint: ctx.accounts.mint.to_account_info(),
+                            from: vesting_vault.to_account_info(),
+                            authority: escrow.to_account_info(),
+                        },
+                        &[&escrow_seeds[..]],
+                    ),
+                    locked,
+                )?;
+                escrow.total_amount = escrow
+                    .total_amount
+                    .checked_sub(locked)
+                    .ok_or(VRGDAError::MathOverflow)?;
+                to_burn -= locked;
+            }
+        }
+
+        if to_burn > 0 {
+            token_interface::burn(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    token_interface::Burn {
+                        mint: ctx.accounts.mint.to_account_info(),
+                        from: ctx.accounts.buyer_ata.to_account_info(),
+                        authority: ctx.accounts.buyer.to_account_info(),
+                    },
+                ),
+                to_burn,
+            )?;
+        }
+
+        let vrgda = &mut ctx.accounts.vrgda;
+        vrgda.total_raised = vrgda
+            .total_raised
+            .checked_sub(refund_amount)
+            .ok_or(VRGDAError::MathOverflow)?;
+
+        Ok(())
+    }
+
+    /// Releases the unlocked part of a vesting escrow to its beneficiary.
+    pub fn claim_vested<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimVested<'info>>,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.vesting_escrow;
+        let claimable = escrow.claimable(Clock::get()?.unix_timestamp)?;
+        require!(claimable > 0, VRGDAError::NothingToClaim);
+        msg!(
+            "Claiming {} of {} vested tokens",
+            claimable,
+            escrow.total_amount
+        );
+
+        let vrgda_key = escrow.vrgda;
+        let beneficiary_key = escrow.beneficiary;
+        let escrow_seeds = &[
+            b"vesting".as_ref(),
+            vrgda_key.as_ref(),
+            beneficiary_key.as_ref(),
+            &[escrow.bump],
+        ];
+        let signer = &[&escrow_seeds[..]];
+
+        extensions::transfer_checked(
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.vesting_vault.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.beneficiary_ata.to_account_info(),
+            ctx.accounts.vesting_escrow.to_account_info(),
+            ctx.remaining_accounts,
+            claimable,
+            ctx.accounts.mint.decimals,
+            signer,
+        )?;
+
+        let escrow = &mut ctx.accounts.vesting_escrow;
+        escrow.claimed_amount = escrow
+            .claimed_amount
+            .checked_add(claimable)
+            .ok_or(VRGDAError::MathOverflow)?;
+
+        Ok(())
+    }
+
+    /// Releases the unlocked part of the reserve allocation to its recipient.
+    pub fn claim_reserve<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimReserve<'info>>,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.reserve_escrow;
+        let claimable = escrow.claimable(Clock::get()?.unix_timestamp)?;
+        require!(claimable > 0, VRGDAError::NothingToClaim);
+        msg!(
+            "Claiming {} of {} reserved tokens",
+            claimable,
+            escrow.total_amount
+        );
+
+        let vrgda_key = escrow.vrgda;
+        let escrow_seeds = &[b"reserve".as_ref(), vrgda_key.as_ref(), &[escrow.bump]];
+        let signer = &[&escrow_seeds[..]];
+
+        extensions::transfer_checked(
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.reserve_vault.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.recipient_ata.to_account_info(),
+            ctx.accounts.reserve_escrow.to_account_info(),
+            ctx.remaining_accounts,
+            claimable,
+            ctx.accounts.mint.decimals,
+            signer,
+        )?;
+
+        let escrow = &mut ctx.accounts.reserve_escrow;
+        escrow.claimed_amount = escrow
+            .claimed_amount
+            .checked_add(claimable)
+            .ok_or(VRGDAError::MathOverflow)?;
+
+        Ok(())
+    }
+
+    /// Changes the curve before anything has sold. The pricing constants are
+    /// worked out again for the new parameters.
+    pub fn update_params(
+        ctx: Context<UpdateParams>,
+        target_price: u128,
+        decay_constant_percent: u64,
+        r: u64,
+    ) -> Result<()> {
+        let vrgda = &mut ctx.accounts.vrgda;
+        require!(vrgda.tokens_sold == 0, VRGDAError::ParamsLocked);
+        VRGDA::validate_params(target_price, decay_constant_percent, r)?;
+        vrgda.target_price = target_price;
+        vrgda.decay_constant_percent = decay_constant_percent;
+        vrgda.schedule = Schedule::LinearSchedule { r };
+        vrgda.current_price =
+            u64::try_from(target_price / ONE).map_err(|_| VRGDAError::PriceOverflow)?;
+        vrgda.update_pricing_constants()?;
+        msg!(
+            "Params: target price {}, decay {}%, r {}",
+            target_price,
+            decay_constant_percent,
+            r
+        );
+        Ok(())
+    }
+
+    /// Sends the auction's proceeds to `payout` from now on.
+    pub fn set_payout(ctx: Context<SetPayout>, payout: Pubkey) -> Result<()> {
+        ctx.accounts.vrgda.payout = payout;
+        msg!("Payout: {:?}", payout);
+        Ok(())
+    }
+
+    /// Replaces the auction's rate limits. Unset limits are not enforced.
+    pub fn set_rate_limits(ctx: Context<SetRateLimits>, rate_limits: RateLimits) -> Result<()> {
+        rate_limits.validate()?;
+        let vrgda = &mut ctx.accounts.vrgda;
+        // Edition sales keep no buyer records to hold a cooldown.
+        require!(
+            vrgda.mint_mode != MintMode::Editions || rate_limits.wallet_cooldown_seconds.is_none(),
+            VRGDAError::MintModeMismatch
+        );
+        vrgda.rate_limits = rate_limits;
+        msg!("Rate limits: {:?}", rate_limits);
+        Ok(())
+    }
+
+    /// Brings an auction created under an older account layout to the current
+    /// one. The authority pays for any realloc, and fields the old layout
+    /// lacked are filled in.
+    pub fn migrate(ctx: Context<Migrate>) -> Result<()> {
+        let account = ctx.accounts.vrgda.to_account_info();
+        let mut vrgda = {
+            let data = account.try_borrow_data()?;
+            require!(
+                data.starts_with(VRGDA::DISCRIMINATOR),
+                ErrorCode::AccountDiscriminatorMismatch
+            );
+            match data.len() - 8 {
+                VRGDA_V0_SPACE => VRGDA::from_v0(VrgdaV0::deserialize(&mut &data[8..])?),
+                VRGDA::INIT_SPACE => VRGDA::try_deserialize(&mut &data[..])?,
+                _ => return err!(VRGDAError::UnsupportedAccountVersion),
+            }
+        };
+        require!(vrgda.version < VRGDA_VERSION, VRGDAError::AlreadyMigrated);
+        require_keys_eq!(
+            vrgda.authority,
+            ctx.accounts.authority.key(),
+            ErrorCode::ConstraintHasOne
+        );
+        // Version 0 lacks the precomputed pricing constants.
+        vrgda.update_pricing_constants()?;
+        vrgda.version = VRGDA_VERSION;
+
+        let space = 8 + VRGDA::INIT_SPACE;
+        extensions::fund_realloc(
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.authority.to_account_info(),
+            account.clone(),
+            space,
+        )?;
+        account.realloc(space, false)?;
+        let mut data = account.try_borrow_mut_data()?;
+        data.fill(0);
+        vrgda.try_serialize(&mut &mut data[..])?;
+        msg!("Migrated auction to layout version {}", VRGDA_VERSION);
+        Ok(())
+    }
+
+    /// Switches commit-reveal mode on or off. While it is on, `reveal` is the
+    /// only way to buy.
+    pub fn set_commit_reveal(
+        ctx: Context<SetCommitReveal>,
+        commit_reveal: Option<CommitRevealConfig>,
+    ) -> Result<()> {
+        let vrgda = &mut ctx.accounts.vrgda;
+        if let Some(config) = &commit_reveal {
+            config.validate()?;
+            require!(
+                vrgda.mint_mode != MintMode::Editions,
+                VRGDAError::MintModeMismatch
+            );
+            require!(
+                !vrgda.needs_buyer_accounts(),
+                VRGDAError::PerBuyerAccountsRequired
+            );
+        }
+        vrgda.commit_reveal = commit_reveal;
+        msg!("Commit-reveal: {:?}", commit_reveal);
+        Ok(())
+    }
+
+    /// First step of handing the auction to `new_authority`, who has to accept.
+    /// Proposing again replaces the pending proposal. If the authority holds the
+    /// metadata update authority, the auction holds it until the handover, so
+    /// the metadata can't be updated in between.
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        let vrgda_key = ctx.accounts.vrgda.key();
+        hand_over_metadata(
+            MetadataHandover {
+                metadata_mode: ctx.accounts.vrgda.metadata_mode,
+                mint: ctx.accounts.mint.to_account_info(),
+                metadata: ctx.accounts.metadata.as_ref(),
+                metadata_program: ctx.accounts.metadata_program.as_ref(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                current_authority: ctx.accounts.authority.to_account_info(),
+            },
+            vrgda_key,
+            &[],
+        )?;
+
+        ctx.accounts.vrgda.pending_authority = Some(new_authority);
+        msg!("Proposed authority: {:?}", new_authority);
+        Ok(())
+    }
+
+    /// Makes the proposed authority the admin, and hands it the metadata update
+    /// authority held since the proposal. Proceeds keep going to `payout` until
+    /// the new authority changes it.
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let new_authority = ctx.accounts.new_authority.key();
+        let vrgda_signer = ctx.accounts.vrgda.signer();
+        let vrgda_seeds = vrgda_signer.seeds();
+        hand_over_metadata(
+            MetadataHandover {
+                metadata_mode: ctx.accounts.vrgda.metadata_mode,
+                mint: ctx.accounts.mint.to_account_info(),
+                metadata: ctx.accounts.metadata.as_ref(),
+                metadata_program: ctx.accounts.metadata_program.as_ref(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                current_authority: ctx.accounts.vrgda.to_account_info(),
+            },
+            new_authority,
+            &[&vrgda_seeds[..]],
+        )?;
+
+        let vrgda = &mut ctx.accounts.vrgda;
+        vrgda.authority = new_authority;
+        vrgda.pending_authority = None;
+        msg!("New authority: {:?}", vrgda.authority);
+        Ok(())
+    }
+
+    /// Closes the auction and returns its rent to the authority, along with the
+    /// unsold tokens in the vault. The proceeds vault has to be empty, so
+    /// `distribute` comes first, or every `refund` when the soft cap failed.
+    /// Transfer hook accounts for the sale mint go in `remaining_accounts`.
+    pub fn close_auction<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseAuction<'info>>,
+    ) -> Result<()> {
+        let vrgda = &ctx.accounts.vrgda;
+        require!(!vrgda.auction_ended, VRGDAError::AuctionEnded);
+        let escrowed = match ctx.accounts.proceeds_vault.as_deref() {
+            Some(vault) => vault.amount,
+            None if vrgda.escrows_proceeds() => return err!(VRGDAError::ProceedsVaultMismatch),
+            None => 0,
+        };
+        if escrowed > 0 {
+            // Buyers' payments stay escrowed until the raise is settled.
+            let now = Clock::get()?.unix_timestamp;
+            require!(!vrgda.soft_cap_failed(now), VRGDAError::RefundsOutstanding);
+            require!(vrgda.soft_cap_met(), VRGDAError::SoftCapNotMet);
+            return err!(VRGDAError::ProceedsNotDistributed);
+        }
+
+        let unsold = ctx.accounts.vrgda_vault.amount;
+        if unsold > 0 {
+            let vrgda_signer = vrgda.signer();
+            let vrgda_seeds = vrgda_signer.seeds();
+            extensions::transfer_checked(
+                &ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.vrgda_vault.to_account_info(),
+                ctx.accounts.mint.to_account_info(),
+                ctx.accounts.authority_ata.to_account_info(),
+                vrgda.to_account_info(),
+                ctx.remaining_accounts,
+                unsold,
+                ctx.accounts.mint.decimals,
+                &[&vrgda_seeds[..]],
+            )?;
+        }
+        msg!("Closed auction, returned {} unsold tokens", unsold);
+        Ok(())
+    }
+
+    // pub fn sell(ctx: Context<Sell>, amount: u64) -> Result<()> {
+    //     // Make sure the seller is not trying to sell 0 tokens.
+    //     require!(amount != 0, VRGDAError::AmountCantBeZero);
+    //     // It makes sense to require that the amount to sell is no more than what has been sold
+    //     // (i.e. the VRGDA’s tokens_sold value), so that we can “reverse” part of the sale.
+    //     require!(amount <= ctx.accounts.vrgda.tokens_sold, VRGDAError::AmountExceedsTotalSupply);
+
+    //     // Get the current time (in seconds) as a fixed-point i128.
+    //     let now = Clock::get()?.unix_timestamp;
+    //     let sold = ctx.accounts.vrgda.tokens_sold;
+    //     let schedule = &ctx.accounts.vrgda.schedule;
+
+    //     // Compute the current price per token according to the VRGDA pricing formula.
+    //     let price_in_sol = ctx.accounts.vrgda.vrgda_price(now, sold)?;
+    //     msg!("Current price per token in SOL: {:?}", price_in_sol);
+
+    //     // Compute the total refund as (price per token) * (amount being sold)
+    //     let refund = calculate_refund(amount, &ctx.accounts.wsol_mint, price_in_sol);
+
+    //     msg!("Total refund amount in SOL: {}", refund);
+
+    //       // Prepare the VRGDA PDA signer seeds – they must match the ones used when minting.
+    //     let vrgda_seeds = &[
+    //         b"vrgda".as_ref(),
+    //         ctx.accounts.vrgda.mint.as_ref(),
+    //         ctx.accounts.authority.key.as_ref(),
+    //         &[ctx.accounts.vrgda.bump],
+    //     ];
+    //     let signer = &[&vrgda_seeds[..]];
+    //     // Burn the tokens from the seller’s token account.
+    //     // This call burns `amount` tokens from seller_ata.
+    //     token_interface::burn(
+    //         CpiContext::new(
+    //             ctx.accounts.token_program.to_account_info(),
+    //             token_interface::Burn {
     //                 mint: ctx.accounts.mint.to_account_info(),
     //                 from: ctx.accounts.seller_ata.to_account_info(),
     //                 authority: ctx.accounts.seller.to_account_info(),
@@ -347,118 +2035,2032 @@ pub mod vrgda {
     //         amount,
     //     )?;
 
-    //     // Transfer WSOL from the VRGDA WSOL vault back to the seller's WSOL account.
-    //     anchor_spl::token::transfer_checked(
-    //         CpiContext::new_with_signer(
-    //             ctx.accounts.token_program.to_account_info(),
-    //             anchor_spl::token::TransferChecked {
-    //                 from: ctx.accounts.vrgda_sol_ata.to_account_info(),
-    //                 to: ctx.accounts.seller_wsol_ata.to_account_info(),
-    //                 // The VRGDA account acts as the authority over the WSOL vault, so we use it as signer.
-    //                 authority: ctx.accounts.vrgda.to_account_info(),
-    //                 mint: ctx.accounts.wsol_mint.to_account_info(),
-    //             },
-    //             signer,
-    //         ),
-    //         refund,
-    //         ctx.accounts.wsol_mint.decimals,
-    //     )?;
+    //     // Transfer WSOL from the VRGDA WSOL vault back to the seller's WSOL account.
+    //     anchor_spl::token::transfer_checked(
+    //         CpiContext::new_with_signer(
+    //             ctx.accounts.token_program.to_account_info(),
+    //             anchor_spl::token::TransferChecked {
+    //                 from: ctx.accounts.vrgda_sol_ata.to_account_info(),
+    //                 to: ctx.accounts.seller_wsol_ata.to_account_info(),
+    //                 // The VRGDA account acts as the authority over the WSOL vault, so we use it as signer.
+    //                 authority: ctx.accounts.vrgda.to_account_info(),
+    //                 mint: ctx.accounts.wsol_mint.to_account_info(),
+    //             },
+    //             signer,
+    //         ),
+    //         refund,
+    //         ctx.accounts.wsol_mint.decimals,
+    //     )?;
+
+    //     // Update VRGDA state: when selling tokens back, the tokens sold decreases
+    //     // and the total supply increases.
+    //     let vrgda = &mut ctx.accounts.vrgda;
+    //     vrgda.tokens_sold = vrgda.tokens_sold.checked_sub(amount).unwrap();
+    //     vrgda.total_supply = vrgda.total_supply.checked_add(amount).unwrap();
+    //     vrgda.current_price = refund;
+
+    //     Ok(())
+    // }
+}
+
+/// `record_sale` for every way of buying except `reveal`, which commit-reveal
+/// auctions are limited to.
+fn book_purchase(vrgda: &mut VRGDA, amount: u64, tokens_out: u64) -> Result<()> {
+    require!(
+        vrgda.commit_reveal.is_none(),
+        VRGDAError::CommitRevealRequired
+    );
+    record_sale(vrgda, amount, tokens_out)
+}
+
+/// Prices `amount` on the curve and records the sale. `tokens_out` is what
+/// leaves the supply, including any transfer fee paid by the vault. The price
+/// to charge ends up in `current_price`.
+fn record_sale(vrgda: &mut VRGDA, amount: u64, tokens_out: u64) -> Result<()> {
+    // Every edition of a drop can be sold, the fungible modes hold back the
+    // last token.
+    if vrgda.mint_mode == MintMode::Editions {
+        require!(
+            tokens_out <= vrgda.total_supply,
+            VRGDAError::AmountExceedsTotalSupply
+        );
+    } else if vrgda.is_capped() {
+        require!(
+            tokens_out < vrgda.total_supply,
+            VRGDAError::AmountExceedsTotalSupply
+        );
+    }
+    require!(!vrgda.auction_ended, VRGDAError::AuctionEnded);
+    vrgda.record_rate_limited_purchase(amount, Clock::get()?.slot)?;
+
+    // Reduce total supply
+    if vrgda.is_capped() {
+        vrgda.total_supply = vrgda
+            .total_supply
+            .checked_sub(tokens_out)
+            .ok_or(VRGDAError::AmountExceedsTotalSupply)?;
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(!vrgda.soft_cap_failed(now), VRGDAError::SoftCapFailed);
+
+    let price = vrgda.sale_price(now, amount)?;
+
+    // Update tokens sold after price calculation
+    vrgda.tokens_sold = vrgda
+        .tokens_sold
+        .checked_add(amount)
+        .ok_or(VRGDAError::MathOverflow)?;
+
+    // Save the updated current_price in state
+    vrgda.current_price = price;
+    vrgda.last_buy_timestamp = now;
+    vrgda.total_raised = vrgda
+        .total_raised
+        .checked_add(price)
+        .ok_or(VRGDAError::MathOverflow)?;
+    Ok(())
+}
+
+/// Account a purchase's payment goes to. Auctions that escrow their proceeds
+/// collect into the proceeds vault until `distribute` is cranked, the others
+/// pay `payout` straight away.
+fn proceeds_destination<'info>(
+    vrgda: &VRGDA,
+    payout_ata: AccountInfo<'info>,
+    proceeds_vault: Option<&InterfaceAccount<'info, TokenAccount>>,
+) -> Result<AccountInfo<'info>> {
+    if !vrgda.escrows_proceeds() {
+        return Ok(payout_ata);
+    }
+    proceeds_vault
+        .map(|vault| vault.to_account_info())
+        .ok_or_else(|| error!(VRGDAError::ProceedsVaultMismatch))
+}
+
+/// Accounts that move a purchase's quote tokens from the buyer to the auction.
+struct Payment<'a, 'info> {
+    buyer: AccountInfo<'info>,
+    buyer_wsol_ata: AccountInfo<'info>,
+    wsol_mint: &'a InterfaceAccount<'info, Mint>,
+    destination: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    hook_accounts: &'a [AccountInfo<'info>],
+}
+
+/// Charges the buyer for a purchase. The auction is credited the full `price`,
+/// the buyer covers the quote mint's transfer fee. Returns what the buyer paid.
+fn collect_payment(payment: Payment, price: u64) -> Result<u64> {
+    let quote_amount = extensions::gross_up(&payment.wsol_mint.to_account_info(), price)?;
+
+    // Transfer SOL from buyer to their WSOL ATA to fund the purchase
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            payment.system_program,
+            anchor_lang::system_program::Transfer {
+                from: payment.buyer.clone(),
+                to: payment.buyer_wsol_ata.clone(),
+            },
+        ),
+        quote_amount,
+    )?;
+
+    // transfer from buyer to vrgda_wallet
+    extensions::transfer_checked(
+        &payment.token_program,
+        payment.buyer_wsol_ata,
+        payment.wsol_mint.to_account_info(),
+        payment.destination,
+        payment.buyer,
+        payment.hook_accounts,
+        quote_amount,
+        payment.wsol_mint.decimals,
+        &[],
+    )?;
+    Ok(quote_amount)
+}
+
+/// Accounts that move an auction's metadata update authority.
+struct MetadataHandover<'a, 'info> {
+    metadata_mode: MetadataMode,
+    mint: AccountInfo<'info>,
+    metadata: Option<&'a UncheckedAccount<'info>>,
+    metadata_program: Option<&'a UncheckedAccount<'info>>,
+    token_program: AccountInfo<'info>,
+    current_authority: AccountInfo<'info>,
+}
+
+/// Moves the metadata update authority from `current_authority` to
+/// `new_authority`. Does nothing if `current_authority` doesn't hold it, as
+/// once the metadata is locked or when another auction's authority created it.
+fn hand_over_metadata(
+    handover: MetadataHandover,
+    new_authority: Pubkey,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    match handover.metadata_mode {
+        MetadataMode::Metaplex => {
+            let metadata = handover
+                .metadata
+                .ok_or(VRGDAError::MetadataAccountsMissing)?;
+            let metadata_program = handover
+                .metadata_program
+                .ok_or(VRGDAError::MetadataAccountsMissing)?;
+
+            let current = mpl_token_metadata::accounts::Metadata::safe_deserialize(
+                &metadata.try_borrow_data()?,
+            )?;
+            require_keys_eq!(
+                current.mint,
+                handover.mint.key(),
+                VRGDAError::AddressesDontMatch
+            );
+            if current.update_authority != handover.current_authority.key() {
+                return Ok(());
+            }
+
+            update_metadata_accounts_v2(
+                CpiContext::new_with_signer(
+                    metadata_program.to_account_info(),
+                    UpdateMetadataAccountsV2 {
+                        metadata: metadata.to_account_info(),
+                        update_authority: handover.current_authority,
+                    },
+                    signer,
+                ),
+                Some(new_authority),
+                None,
+                None,
+                None,
+            )?;
+        }
+        MetadataMode::Token2022 => {
+            if extensions::token_metadata_update_authority(&handover.mint)?
+                != Some(handover.current_authority.key())
+            {
+                return Ok(());
+            }
+
+            token_interface::token_metadata_update_authority(
+                CpiContext::new_with_signer(
+                    handover.token_program.clone(),
+                    token_interface::TokenMetadataUpdateAuthority {
+                        program_id: handover.token_program,
+                        metadata: handover.mint,
+                        current_authority: handover.current_authority.clone(),
+                        new_authority: handover.current_authority,
+                    },
+                    signer,
+                ),
+                OptionalNonZeroPubkey::try_from(Some(new_authority))?,
+            )?;
+        }
+    }
+    msg!("Metadata update authority: {:?}", new_authority);
+    Ok(())
+}
+
+/// Accounts that hand purchased tokens over to a buyer.
+struct Delivery<'a, 'info> {
+    vrgda: &'a Account<'info, VRGDA>,
+    mint: &'a InterfaceAccount<'info, Mint>,
+    vrgda_vault: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    hook_accounts: &'a [AccountInfo<'info>],
+}
+
+/// Sends a purchase of `amount` to `to`. Pre-mint auctions transfer `tokens_out`,
+/// the amount grossed up for the transfer fee, out of the vault, the others mint.
+fn deliver_tokens<'info>(
+    delivery: &Delivery<'_, 'info>,
+    to: AccountInfo<'info>,
+    amount: u64,
+    tokens_out: u64,
+) -> Result<()> {
+    let vrgda = delivery.vrgda;
+    let vrgda_signer = vrgda.signer();
+    let vrgda_seeds = vrgda_signer.seeds();
+    let signer = &[&vrgda_seeds[..]];
+
+    if vrgda.mint_mode == MintMode::PreMint {
+        extensions::transfer_checked(
+            &delivery.token_program,
+            delivery.vrgda_vault.clone(),
+            delivery.mint.to_account_info(),
+            to,
+            vrgda.to_account_info(),
+            delivery.hook_accounts,
+            tokens_out,
+            delivery.mint.decimals,
+            signer,
+        )
+    } else {
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                delivery.token_program.clone(),
+                anchor_spl::token_interface::MintTo {
+                    mint: delivery.mint.to_account_info(),
+                    to,
+                    authority: vrgda.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )
+    }
+}
+
+/// The quote escrow of a limit order, DCA order or purchase commit, owned by
+/// that account's PDA.
+struct OrderEscrow<'a, 'info> {
+    order: AccountInfo<'info>,
+    escrow: &'a InterfaceAccount<'info, TokenAccount>,
+    wsol_mint: &'a InterfaceAccount<'info, Mint>,
+    owner: AccountInfo<'info>,
+    owner_wsol_ata: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    hook_accounts: &'a [AccountInfo<'info>],
+}
+
+/// Sends `amount` left in the escrow back to the order's owner and closes the
+/// escrow, returning its rent to the owner as well.
+fn release_order_escrow(escrow: OrderEscrow, amount: u64, signer: &[&[&[u8]]]) -> Result<()> {
+    if amount > 0 {
+        extensions::transfer_checked(
+            &escrow.token_program,
+            escrow.escrow.to_account_info(),
+            escrow.wsol_mint.to_account_info(),
+            escrow.owner_wsol_ata,
+            escrow.order.clone(),
+            escrow.hook_accounts,
+            amount,
+            escrow.wsol_mint.decimals,
+            signer,
+        )?;
+    }
+
+    token_interface::close_account(CpiContext::new_with_signer(
+        escrow.token_program,
+        token_interface::CloseAccount {
+            account: escrow.escrow.to_account_info(),
+            destination: escrow.owner,
+            authority: escrow.order,
+        },
+        signer,
+    ))
+}
+
+/// Accounts shared by `initialize_vrgda` and `initialize_vrgda_with_mint` once
+/// the sale mint and its vaults exist.
+struct AuctionSetup<'a, 'info> {
+    authority: AccountInfo<'info>,
+    vrgda: &'a mut Account<'info, VRGDA>,
+    vrgda_bump: u8,
+    vrgda_vault: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    authority_token_account: Option<AccountInfo<'info>>,
+    wsol_mint: AccountInfo<'info>,
+    proceeds_vault: Option<AccountInfo<'info>>,
+    reserve_escrow: Option<&'a mut Account<'info, VestingEscrow>>,
+    reserve_escrow_bump: Option<u8>,
+    reserve_vault: Option<AccountInfo<'info>>,
+    metadata: Option<AccountInfo<'info>>,
+    metadata_program: Option<AccountInfo<'info>>,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    rent: AccountInfo<'info>,
+    hook_accounts: &'a [AccountInfo<'info>],
+}
+
+fn setup_auction(mut accounts: AuctionSetup, params: AuctionParams) -> Result<()> {
+    let AuctionParams {
+        auction_id,
+        target_price,
+        decay_constant_percent,
+        vrgda_start_timestamp,
+        total_supply,
+        r,
+        name,
+        symbol,
+        uri,
+        payees,
+        vesting,
+        reserve,
+        soft_cap,
+        mint_mode,
+        metadata_mode,
+    } = params;
+
+    require!(
+        mint_mode != MintMode::Editions,
+        VRGDAError::MintModeMismatch
+    );
+    VRGDA::validate_params(target_price, decay_constant_percent, r)?;
+    // Perpetual auctions mint on demand and have no supply to sell out of.
+    require!(
+        total_supply > 0 || mint_mode == MintMode::Perpetual,
+        VRGDAError::InvalidTotalSupply
+    );
+    VRGDA::validate_metadata(&name, &symbol, &uri)?;
+    VRGDA::validate_payees(&payees)?;
+    if let Some(vesting) = &vesting {
+        vesting.validate()?;
+    }
+    if let Some(reserve) = &reserve {
+        require!(reserve.amount > 0, VRGDAError::InvalidReserve);
+        reserve.unlock.validate()?;
+    }
+    // let vrgda = &mut accounts.vrgda;
+    accounts.vrgda.version = VRGDA_VERSION;
+    accounts.vrgda.total_supply = total_supply;
+    accounts.vrgda.target_price = target_price;
+    accounts.vrgda.decay_constant_percent = decay_constant_percent;
+    accounts.vrgda.schedule = Schedule::LinearSchedule { r };
+    accounts.vrgda.update_pricing_constants()?;
+    accounts.vrgda.tokens_sold = 0;
+    accounts.vrgda.auction_ended = false;
+    accounts.vrgda.current_price =
+        u64::try_from(target_price / ONE).map_err(|_| VRGDAError::PriceOverflow)?;
+    accounts.vrgda.buy_window_time = 60; // 60 seconds hardcoded for now
+    accounts.vrgda.created_at_timestamp = Clock::get()?.unix_timestamp.cast::<i64>()?;
+
+    accounts.vrgda.vrgda_start_timestamp =
+        if vrgda_start_timestamp < Clock::get()?.unix_timestamp.cast::<i64>()? {
+            Clock::get()?.unix_timestamp.cast::<i64>()?
+        } else {
+            vrgda_start_timestamp
+        };
+
+    accounts.vrgda.authority = accounts.authority.key();
+    accounts.vrgda.payout = accounts.authority.key();
+    accounts.vrgda.creator = accounts.authority.key();
+    accounts.vrgda.pending_authority = None;
+    accounts.vrgda.mint = accounts.mint.key();
+    accounts.vrgda.bump = accounts.vrgda_bump;
+    accounts.vrgda.auction_id = auction_id;
+    accounts.vrgda.payees = payees;
+    accounts.vrgda.vesting = vesting;
+    if let Some(soft_cap) = &soft_cap {
+        require!(
+            soft_cap.amount > 0 && soft_cap.deadline > accounts.vrgda.vrgda_start_timestamp,
+            VRGDAError::InvalidSoftCap
+        );
+    }
+    accounts.vrgda.soft_cap = soft_cap;
+    require!(
+        accounts.proceeds_vault.is_some() == accounts.vrgda.escrows_proceeds(),
+        VRGDAError::ProceedsVaultMismatch
+    );
+    accounts.vrgda.mint_mode = mint_mode;
+    accounts.vrgda.metadata_mode = metadata_mode;
+
+    let mint_seed = accounts.mint.key();
+    let authority_seed = accounts.authority.key();
+    let auction_id_seed = auction_id.to_le_bytes();
+
+    let signer_seeds = &[
+        b"vrgda".as_ref(),
+        mint_seed.as_ref(),
+        authority_seed.as_ref(),
+        auction_id_seed.as_ref(),
+        &[accounts.vrgda_bump],
+    ];
+
+    let seeds = &[&signer_seeds[..]];
+
+    // A token that already backs an auction keeps its metadata, later auctions
+    // ignore `name`, `symbol` and `uri`.
+    let has_metadata = match metadata_mode {
+        MetadataMode::Metaplex => accounts
+            .metadata
+            .as_ref()
+            .is_some_and(|metadata| !metadata.data_is_empty()),
+        MetadataMode::Token2022 => extensions::has_token_metadata(&accounts.mint)?,
+    };
+
+    match metadata_mode {
+        _ if has_metadata => {}
+        MetadataMode::Metaplex => {
+            let token_data = DataV2 {
+                name: name,
+                symbol: symbol,
+                uri: uri,
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            };
+
+            let metadata = accounts
+                .metadata
+                .as_ref()
+                .ok_or(VRGDAError::MetadataAccountsMissing)?;
+            let metadata_program = accounts
+                .metadata_program
+                .as_ref()
+                .ok_or(VRGDAError::MetadataAccountsMissing)?;
+
+            let metadata_ctx = CpiContext::new_with_signer(
+                metadata_program.to_account_info(),
+                CreateMetadataAccountsV3 {
+                    metadata: metadata.to_account_info(),
+                    mint: accounts.mint.to_account_info(),
+                    mint_authority: accounts.authority.to_account_info(),
+                    update_authority: accounts.authority.to_account_info(),
+                    payer: accounts.authority.to_account_info(),
+                    system_program: accounts.system_program.to_account_info(),
+                    rent: accounts.rent.to_account_info(),
+                },
+                seeds,
+            );
+
+            create_metadata_accounts_v3(metadata_ctx, token_data, false, true, None)?;
+        }
+        MetadataMode::Token2022 => {
+            let mint_info = accounts.mint.to_account_info();
+            require_keys_eq!(
+                accounts.token_program.key(),
+                anchor_spl::token_2022::ID,
+                VRGDAError::MetadataPointerMismatch
+            );
+            require!(
+                extensions::metadata_pointer(&mint_info)? == Some(mint_seed),
+                VRGDAError::MetadataPointerMismatch
+            );
+
+            let space = mint_info.data_len()
+                + extensions::token_metadata_space(
+                    mint_seed,
+                    authority_seed,
+                    &name,
+                    &symbol,
+                    &uri,
+                )?;
+            extensions::fund_realloc(
+                accounts.system_program.to_account_info(),
+                accounts.authority.to_account_info(),
+                mint_info.clone(),
+                space,
+            )?;
+
+            token_interface::token_metadata_initialize(
+                CpiContext::new(
+                    accounts.token_program.to_account_info(),
+                    token_interface::TokenMetadataInitialize {
+                        program_id: accounts.token_program.to_account_info(),
+                        metadata: mint_info.clone(),
+                        update_authority: accounts.authority.to_account_info(),
+                        mint_authority: accounts.authority.to_account_info(),
+                        mint: mint_info,
+                    },
+                ),
+                name,
+                symbol,
+                uri,
+            )?;
+        }
+    }
+
+    let (mint_authority, decimals) = {
+        let data = accounts.mint.try_borrow_data()?;
+        let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+        (
+            Option::<Pubkey>::from(mint.base.mint_authority),
+            mint.base.decimals,
+        )
+    };
+
+    if mint_authority == Some(authority_seed) {
+        token_interface::set_authority(
+            CpiContext::new(
+                accounts.token_program.to_account_info(),
+                anchor_spl::token_interface::SetAuthority {
+                    current_authority: accounts.authority.to_account_info(),
+                    account_or_mint: accounts.mint.to_account_info(),
+                },
+            ),
+            anchor_spl::token_interface::spl_token_2022::instruction::AuthorityType::MintTokens,
+            Some(accounts.vrgda.key()),
+        )?;
+
+        // Lazy and perpetual auctions mint on every purchase instead.
+        if mint_mode == MintMode::PreMint {
+            token_interface::mint_to(
+                CpiContext::new_with_signer(
+                    accounts.token_program.to_account_info(),
+                    anchor_spl::token_interface::MintTo {
+                        mint: accounts.mint.to_account_info(),
+                        to: accounts.vrgda_vault.to_account_info(),
+                        authority: accounts.vrgda.to_account_info(),
+                    },
+                    seeds,
+                ),
+                total_supply,
+            )?;
+        }
+    } else {
+        // Another auction of this token already holds the mint authority, so this
+        // one sells tokens the authority deposits from its own account.
+        require!(
+            mint_mode == MintMode::PreMint && reserve.is_none(),
+            VRGDAError::MintAuthorityUnavailable
+        );
+        let authority_token_account = accounts
+            .authority_token_account
+            .as_ref()
+            .ok_or(VRGDAError::MintAuthorityUnavailable)?;
+        extensions::transfer_checked(
+            &accounts.token_program,
+            authority_token_account.clone(),
+            accounts.mint.clone(),
+            accounts.vrgda_vault.clone(),
+            accounts.authority.clone(),
+            accounts.hook_accounts,
+            extensions::gross_up(&accounts.mint, total_supply)?,
+            decimals,
+            &[],
+        )?;
+    }
+
+    if let Some(reserve) = reserve {
+        let vrgda_key = accounts.vrgda.key();
+        let start_timestamp = accounts.vrgda.vrgda_start_timestamp;
+        let reserve_vault = accounts
+            .reserve_vault
+            .as_ref()
+            .ok_or(VRGDAError::ReserveAccountsMissing)?;
+        let escrow = accounts
+            .reserve_escrow
+            .as_mut()
+            .ok_or(VRGDAError::ReserveAccountsMissing)?;
+
+        escrow.vrgda = vrgda_key;
+        escrow.mint = mint_seed;
+        escrow.beneficiary = reserve.recipient;
+        escrow.config = reserve.unlock;
+        escrow.total_amount = reserve.amount;
+        escrow.claimed_amount = 0;
+        escrow.start_timestamp = start_timestamp;
+        escrow.bump = accounts
+            .reserve_escrow_bump
+            .ok_or(VRGDAError::ReserveAccountsMissing)?;
+
+        // The reserve is minted straight into its escrow so it never touches the curve.
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                anchor_spl::token_interface::MintTo {
+                    mint: accounts.mint.to_account_info(),
+                    to: reserve_vault.to_account_info(),
+                    authority: accounts.vrgda.to_account_info(),
+                },
+                seeds,
+            ),
+            reserve.amount,
+        )?;
+        accounts.vrgda.reserve_amount = reserve.amount;
+    }
+
+    msg!("VRGDA PDA: {:?}", accounts.vrgda.key());
+    msg!("VRGDA Mint: {:?}", accounts.vrgda.mint);
+    msg!("VRGDA Authority: {:?}", accounts.vrgda.authority);
+    msg!("VRGDA VAULT: {:?}", accounts.vrgda_vault.key());
+    msg!("VRGDA TOTAL SUPPLY: {}", accounts.vrgda.total_supply);
+    msg!("VRGDA TARGET PRICE: {}", accounts.vrgda.target_price);
+    msg!(
+        "VRGDA DECAY CONSTANT: {}",
+        accounts.vrgda.decay_constant_percent
+    );
+    msg!("VRGDA SCHEDULE: {:?}", accounts.vrgda.schedule);
+    msg!("VRGDA PAYEES: {:?}", accounts.vrgda.payees);
+    msg!("VRGDA VESTING: {:?}", accounts.vrgda.vesting);
+    msg!("VRGDA RESERVE: {}", accounts.vrgda.reserve_amount);
+    msg!("VRGDA SOFT CAP: {:?}", accounts.vrgda.soft_cap);
+    msg!("VRGDA MINT MODE: {:?}", accounts.vrgda.mint_mode);
+    msg!("VRGDA METADATA MODE: {:?}", accounts.vrgda.metadata_mode);
+    msg!(
+        "VRGDA TRANSFER HOOKS: sale {} quote {}",
+        extensions::has_transfer_hook(&accounts.mint.to_account_info())?,
+        extensions::has_transfer_hook(&accounts.wsol_mint.to_account_info())?
+    );
+    msg!(
+        "VRGDA CREATED AT TIMESTAMP: {}",
+        accounts.vrgda.created_at_timestamp
+    );
+    msg!(
+        "VRDGA START TIMESTAMP: {}",
+        accounts.vrgda.vrgda_start_timestamp
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(params: AuctionParams)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VRGDA::INIT_SPACE,
+        seeds = [
+            b"vrgda".as_ref(),
+            mint.key().as_ref(),
+            authority.key().as_ref(),
+            &params.auction_id.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub vrgda_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Only required when another auction of `mint` holds its mint authority. The
+    /// supply is then transferred in from here instead of minted.
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = authority,
+        token::token_program = token_program,
+    )]
+    pub authority_token_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program,
+    )]
+    pub vrgda_sol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Holds payments until `distribute`. Only passed, and created, when the
+    /// auction has payees or a soft cap.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub proceeds_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Only required when a reserve is configured.
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VestingEscrow::INIT_SPACE,
+        seeds = [b"reserve".as_ref(), vrgda.key().as_ref()],
+        bump,
+    )]
+    pub reserve_escrow: Option<Box<Account<'info, VestingEscrow>>>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = reserve_escrow,
+        associated_token::token_program = token_program,
+    )]
+    pub reserve_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        // address = WSOL_MINT,
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Validated by Metaplex - stores token metadata. Only required in
+    /// `MetadataMode::Metaplex`.
+    #[account(mut)]
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: This is the Metaplex Token Metadata program
+    #[account(address = METADATA_PROGRAM_ID)]
+    pub metadata_program: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: AuctionParams)]
+pub struct InitializeWithMint<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Created and initialized as the sale mint in the handler.
+    #[account(
+        mut,
+        seeds = [b"mint".as_ref(), authority.key().as_ref(), params.symbol.as_bytes()],
+        bump,
+    )]
+    pub mint: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VRGDA::INIT_SPACE,
+        seeds = [
+            b"vrgda".as_ref(),
+            mint.key().as_ref(),
+            authority.key().as_ref(),
+            &params.auction_id.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
+    /// CHECK: Created by the associated token program once the mint exists.
+    #[account(mut)]
+    pub vrgda_vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program,
+    )]
+    pub vrgda_sol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Holds payments until `distribute`. Only passed, and created, when the
+    /// auction has payees or a soft cap.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub proceeds_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Only required when a reserve is configured.
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VestingEscrow::INIT_SPACE,
+        seeds = [b"reserve".as_ref(), vrgda.key().as_ref()],
+        bump,
+    )]
+    pub reserve_escrow: Option<Box<Account<'info, VestingEscrow>>>,
+
+    /// CHECK: Created by the associated token program once the mint exists.
+    #[account(mut)]
+    pub reserve_vault: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Validated by Metaplex - stores token metadata. Only required in
+    /// `MetadataMode::Metaplex`.
+    #[account(mut)]
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: This is the Metaplex Token Metadata program
+    #[account(address = METADATA_PROGRAM_ID)]
+    pub metadata_program: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: EditionDropParams)]
+pub struct InitializeEditionDrop<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VRGDA::INIT_SPACE,
+        seeds = [
+            b"vrgda".as_ref(),
+            mint.key().as_ref(),
+            authority.key().as_ref(),
+            &params.auction_id.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
+    /// CHECK: Only identifies the drop in the VRGDA seeds, usually the collection
+    /// mint. Nothing is minted from it.
+    pub mint: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program,
+    )]
+    pub vrgda_sol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Holds payments until `distribute`. Only passed, and created, when the
+    /// auction has payees or a soft cap.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub proceeds_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: InterfaceAccount<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMetadata<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority,
+        has_one = mint,
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: Checked against the mint after deserializing. Only required in
+    /// `MetadataMode::Metaplex`.
+    #[account(mut)]
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: This is the Metaplex Token Metadata program
+    #[account(address = METADATA_PROGRAM_ID)]
+    pub metadata_program: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Buy<'info> {
+    /// Pays for the purchase.
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// CHECK: Any wallet. Receives the tokens and is tracked as the purchaser.
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = mint,
+        has_one = payout,
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
+    #[account(
+        mut,
+        address = vrgda.mint,
+        mint::token_program = token_program,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        // address = WSOL_MINT,
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_wsol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program,
+        // constraint = buyer_ata.owner == buyer.key() @ VRGDAError::AddressesDontMatch,
+    )]
+    pub recipient_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+        // constraint = vrgda_vault.owner == vrgda.key() @ VRGDAError::AddressesDontMatch,
+    )]
+    pub vrgda_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = payout,
+        associated_token::token_program = token_program,
+    )]
+    pub vrgda_sol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Only required when the auction escrows its proceeds, see
+    /// `VRGDA::escrows_proceeds`.
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub proceeds_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Only required when the auction has a soft cap.
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + BuyerRecord::INIT_SPACE,
+        seeds = [b"buyer".as_ref(), vrgda.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub buyer_record: Option<Box<Account<'info, BuyerRecord>>>,
+
+    /// Only required when the auction has a vesting config.
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + VestingEscrow::INIT_SPACE,
+        seeds = [b"vesting".as_ref(), vrgda.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub vesting_escrow: Option<Box<Account<'info, VestingEscrow>>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = vesting_escrow,
+        associated_token::token_program = token_program,
+    )]
+    pub vesting_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: checked in the constraint has_one
+    #[account(
+        address = vrgda.payout,
+    )]
+    pub payout: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
+    /// Quote mint, its transfer fee is added to the cost.
+    #[account(mint::token_program = token_program)]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct BuyBatch<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = mint,
+        has_one = payout,
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
+    #[account(
+        mut,
+        address = vrgda.mint,
+        mint::token_program = token_program,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_wsol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub vrgda_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = payout,
+        associated_token::token_program = token_program,
+    )]
+    pub vrgda_sol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Only required when the auction escrows its proceeds, see
+    /// `VRGDA::escrows_proceeds`.
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub proceeds_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: checked in the constraint has_one
+    #[account(
+        address = vrgda.payout,
+    )]
+    pub payout: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct PlaceLimitOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + LimitOrder::INIT_SPACE,
+        seeds = [
+            b"limit_order".as_ref(),
+            vrgda.key().as_ref(),
+            owner.key().as_ref(),
+            order_id.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub limit_order: Box<Account<'info, LimitOrder>>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = limit_order,
+        associated_token::token_program = token_program,
+    )]
+    pub order_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        address = vrgda.mint,
+        mint::token_program = token_program,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_wsol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Created up front so executing the order costs the cranker no rent.
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLimitOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds = [
+            b"limit_order".as_ref(),
+            limit_order.vrgda.as_ref(),
+            owner.key().as_ref(),
+            limit_order.order_id.to_le_bytes().as_ref(),
+        ],
+        bump = limit_order.bump,
+    )]
+    pub limit_order: Box<Account<'info, LimitOrder>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = limit_order,
+        associated_token::token_program = token_program,
+    )]
+    pub order_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_wsol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteLimitOrder<'info> {
+    /// Anyone. Pays for its own tip account if needed.
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    /// CHECK: checked against the order's owner
+    #[account(
+        mut,
+        address = limit_order.owner,
+    )]
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = mint,
+        has_one = payout,
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = vrgda,
+        has_one = owner,
+        seeds = [
+            b"limit_order".as_ref(),
+            vrgda.key().as_ref(),
+            owner.key().as_ref(),
+            limit_order.order_id.to_le_bytes().as_ref(),
+        ],
+        bump = limit_order.bump,
+    )]
+    pub limit_order: Box<Account<'info, LimitOrder>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = limit_order,
+        associated_token::token_program = token_program,
+    )]
+    pub order_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = vrgda.mint,
+        mint::token_program = token_program,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_wsol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = cranker,
+        associated_token::token_program = token_program,
+    )]
+    pub cranker_wsol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub vrgda_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = payout,
+        associated_token::token_program = token_program,
+    )]
+    pub vrgda_sol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Only required when the auction escrows its proceeds, see
+    /// `VRGDA::escrows_proceeds`.
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub proceeds_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: checked in the constraint has_one
+    #[account(
+        address = vrgda.payout,
+    )]
+    pub payout: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct CreateDcaOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + DcaOrder::INIT_SPACE,
+        seeds = [
+            b"dca".as_ref(),
+            vrgda.key().as_ref(),
+            owner.key().as_ref(),
+            order_id.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub dca_order: Box<Account<'info, DcaOrder>>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = dca_order,
+        associated_token::token_program = token_program,
+    )]
+    pub order_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        address = vrgda.mint,
+        mint::token_program = token_program,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_wsol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Created up front so executing slices costs the cranker no rent.
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelDcaOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds = [
+            b"dca".as_ref(),
+            dca_order.vrgda.as_ref(),
+            owner.key().as_ref(),
+            dca_order.order_id.to_le_bytes().as_ref(),
+        ],
+        bump = dca_order.bump,
+    )]
+    pub dca_order: Box<Account<'info, DcaOrder>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = dca_order,
+        associated_token::token_program = token_program,
+    )]
+    pub order_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_wsol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteDca<'info> {
+    pub cranker: Signer<'info>,
+
+    /// CHECK: checked against the order's owner
+    #[account(
+        mut,
+        address = dca_order.owner,
+    )]
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = mint,
+        has_one = payout,
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
+    #[account(
+        mut,
+        has_one = vrgda,
+        has_one = owner,
+        seeds = [
+            b"dca".as_ref(),
+            vrgda.key().as_ref(),
+            owner.key().as_ref(),
+            dca_order.order_id.to_le_bytes().as_ref(),
+        ],
+        bump = dca_order.bump,
+    )]
+    pub dca_order: Box<Account<'info, DcaOrder>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = dca_order,
+        associated_token::token_program = token_program,
+    )]
+    pub order_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = vrgda.mint,
+        mint::token_program = token_program,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_wsol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub vrgda_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = payout,
+        associated_token::token_program = token_program,
+    )]
+    pub vrgda_sol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Only required when the auction escrows its proceeds, see
+    /// `VRGDA::escrows_proceeds`.
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub proceeds_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: checked in the constraint has_one
+    #[account(
+        address = vrgda.payout,
+    )]
+    pub payout: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(intent: PurchaseIntent)]
+pub struct BuyWithIntent<'info> {
+    /// Submits the transaction and pays its fees and rent.
+    #[account(mut)]
+    pub relayer: Signer<'info>,
 
-    //     // Update VRGDA state: when selling tokens back, the tokens sold decreases
-    //     // and the total supply increases.
-    //     let vrgda = &mut ctx.accounts.vrgda;
-    //     vrgda.tokens_sold = vrgda.tokens_sold.checked_sub(amount).unwrap();
-    //     vrgda.total_supply = vrgda.total_supply.checked_add(amount).unwrap();
-    //     vrgda.current_price = refund;
+    /// CHECK: Signature over the intent is checked through the ed25519 precompile.
+    pub buyer: UncheckedAccount<'info>,
 
-    //     Ok(())
-    // }
+    #[account(
+        mut,
+        has_one = mint,
+        has_one = payout,
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + IntentNonce::INIT_SPACE,
+        seeds = [
+            b"intent".as_ref(),
+            vrgda.key().as_ref(),
+            buyer.key().as_ref(),
+            intent.nonce.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub intent_nonce: Box<Account<'info, IntentNonce>>,
+
+    #[account(
+        mut,
+        address = vrgda.mint,
+        mint::token_program = token_program,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Has the auction PDA approved as delegate for the payment.
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_wsol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        associated_token::mint = mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub vrgda_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = payout,
+        associated_token::token_program = token_program,
+    )]
+    pub vrgda_sol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Only required when the auction escrows its proceeds, see
+    /// `VRGDA::escrows_proceeds`.
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub proceeds_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: checked in the constraint has_one
+    #[account(
+        address = vrgda.payout,
+    )]
+    pub payout: UncheckedAccount<'info>,
+
+    /// CHECK: The instructions sysvar, used to find the ed25519 precompile call.
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct Initialize<'info> {
+pub struct Commit<'info> {
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub buyer: Signer<'info>,
+
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
 
     #[account(
         init,
-        payer = authority,
-        space = 8 + VRGDA::INIT_SPACE,
-        seeds = [b"vrgda".as_ref(), mint.key().as_ref(), authority.key().as_ref()],
-        bump
+        payer = buyer,
+        space = 8 + PurchaseCommit::INIT_SPACE,
+        seeds = [b"commit".as_ref(), vrgda.key().as_ref(), buyer.key().as_ref()],
+        bump,
+    )]
+    pub purchase_commit: Box<Account<'info, PurchaseCommit>>,
+
+    #[account(
+        init,
+        payer = buyer,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = purchase_commit,
+        associated_token::token_program = token_program,
+    )]
+    pub commit_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_wsol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Reveal<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = mint,
+        has_one = payout,
     )]
     pub vrgda: Box<Account<'info, state::VRGDA>>,
 
+    #[account(
+        mut,
+        close = buyer,
+        has_one = vrgda,
+        has_one = buyer,
+        seeds = [b"commit".as_ref(), vrgda.key().as_ref(), buyer.key().as_ref()],
+        bump = purchase_commit.bump,
+    )]
+    pub purchase_commit: Box<Account<'info, PurchaseCommit>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = purchase_commit,
+        associated_token::token_program = token_program,
+    )]
+    pub commit_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = vrgda.mint,
+        mint::token_program = token_program,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_wsol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
     #[account(
         init_if_needed,
-        payer = authority,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
         associated_token::mint = mint,
         associated_token::authority = vrgda,
         associated_token::token_program = token_program,
     )]
-    pub vrgda_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub vrgda_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = payout,
+        associated_token::token_program = token_program,
+    )]
+    pub vrgda_sol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Only required when the auction escrows its proceeds, see
+    /// `VRGDA::escrows_proceeds`.
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub proceeds_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: checked in the constraint has_one
+    #[account(
+        address = vrgda.payout,
+    )]
+    pub payout: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimCommit<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        close = buyer,
+        has_one = buyer,
+        seeds = [
+            b"commit".as_ref(),
+            purchase_commit.vrgda.as_ref(),
+            buyer.key().as_ref(),
+        ],
+        bump = purchase_commit.bump,
+    )]
+    pub purchase_commit: Box<Account<'info, PurchaseCommit>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = purchase_commit,
+        associated_token::token_program = token_program,
+    )]
+    pub commit_escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_wsol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct BuyEdition<'info> {
+    /// Pays for the purchase.
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// CHECK: Any wallet. Receives the NFT.
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = payout,
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    pub buyer_wsol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
-        mint::token_program = token_program,
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = payout,
+        associated_token::token_program = token_program,
     )]
-    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    pub vrgda_sol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
 
+    /// Only required when the auction escrows its proceeds, see
+    /// `VRGDA::escrows_proceeds`.
     #[account(
         mut,
         associated_token::mint = wsol_mint,
-        associated_token::authority = authority,
+        associated_token::authority = vrgda,
         associated_token::token_program = token_program,
     )]
-    pub vrgda_sol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub proceeds_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
+    /// Mint of the NFT being sold, one per edition index.
     #[account(
-        // address = WSOL_MINT,
-        mint::token_program = token_program,
+        init,
+        payer = buyer,
+        seeds = [
+            b"edition".as_ref(),
+            vrgda.key().as_ref(),
+            &vrgda.tokens_sold.to_le_bytes(),
+        ],
+        bump,
+        mint::decimals = 0,
+        mint::authority = vrgda,
+        mint::freeze_authority = vrgda,
+        mint::token_program = edition_token_program,
     )]
-    pub wsol_mint: InterfaceAccount<'info, Mint>,
+    pub edition_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = buyer,
+        associated_token::mint = edition_mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = edition_token_program,
+    )]
+    pub recipient_edition_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Validated by Metaplex - created for `edition_mint`.
+    #[account(mut)]
+    pub edition_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by Metaplex - created for `edition_mint`.
+    #[account(mut)]
+    pub master_edition: UncheckedAccount<'info>,
+
+    /// CHECK: Checked against the drop's collection. Only required when it has one.
+    pub collection_mint: Option<UncheckedAccount<'info>>,
 
-    /// CHECK: Validated by Metaplex - stores token metadata
+    /// CHECK: Validated by Metaplex.
     #[account(mut)]
-    pub metadata: UncheckedAccount<'info>,
+    pub collection_metadata: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Validated by Metaplex.
+    pub collection_master_edition: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Validated by Metaplex. Only needed when the VRGDA is a delegated
+    /// collection authority rather than the collection's update authority.
+    pub collection_authority_record: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: checked in the constraint has_one
+    #[account(
+        address = vrgda.payout,
+    )]
+    pub payout: UncheckedAccount<'info>,
 
     /// CHECK: This is the Metaplex Token Metadata program
     #[account(address = METADATA_PROGRAM_ID)]
     pub metadata_program: UncheckedAccount<'info>,
 
-    pub system_program: Program<'info, System>,
     pub token_program: Interface<'info, TokenInterface>,
+    /// Metaplex master editions only support the original token program.
+    pub edition_token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
     pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct Buy<'info> {
+pub struct Distribute<'info> {
+    pub cranker: Signer<'info>,
+
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub proceeds_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Refund<'info> {
     #[account(mut)]
     pub buyer: Signer<'info>,
 
     #[account(
         mut,
         has_one = mint,
-        has_one = authority,
     )]
     pub vrgda: Box<Account<'info, state::VRGDA>>,
 
     #[account(
         mut,
-        address = vrgda.mint,
+        close = buyer,
+        has_one = vrgda,
+        has_one = buyer,
+        seeds = [b"buyer".as_ref(), vrgda.key().as_ref(), buyer.key().as_ref()],
+        bump = buyer_record.bump,
+    )]
+    pub buyer_record: Box<Account<'info, BuyerRecord>>,
+
+    #[account(
+        mut,
         mint::token_program = token_program,
     )]
     pub mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        // address = WSOL_MINT,
         mint::token_program = token_program,
     )]
     pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        init_if_needed,
-        payer = buyer,
+        mut,
+        associated_token::mint = wsol_mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub proceeds_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
         associated_token::mint = wsol_mint,
         associated_token::authority = buyer,
         associated_token::token_program = token_program,
@@ -466,59 +4068,245 @@ pub struct Buy<'info> {
     pub buyer_wsol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
-        init_if_needed,
-        payer = buyer,
+        mut,
         associated_token::mint = mint,
         associated_token::authority = buyer,
         associated_token::token_program = token_program,
-        // constraint = buyer_ata.owner == buyer.key() @ VRGDAError::AddressesDontMatch,
     )]
     pub buyer_ata: Box<InterfaceAccount<'info, TokenAccount>>,
 
+    /// Only required for vesting auctions.
+    #[account(
+        mut,
+        seeds = [b"vesting".as_ref(), vrgda.key().as_ref(), buyer.key().as_ref()],
+        bump = vesting_escrow.bump,
+    )]
+    pub vesting_escrow: Option<Box<Account<'info, VestingEscrow>>>,
+
     #[account(
         mut,
         associated_token::mint = mint,
-        associated_token::authority = vrgda,
+        associated_token::authority = vesting_escrow,
         associated_token::token_program = token_program,
-        // constraint = vrgda_vault.owner == vrgda.key() @ VRGDAError::AddressesDontMatch,
     )]
-    pub vrgda_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub vesting_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
 
     #[account(
         mut,
-        associated_token::mint = wsol_mint,
-        associated_token::authority = authority,
+        has_one = beneficiary,
+        has_one = mint,
+        seeds = [b"vesting".as_ref(), vesting_escrow.vrgda.as_ref(), beneficiary.key().as_ref()],
+        bump = vesting_escrow.bump,
+    )]
+    pub vesting_escrow: Box<Account<'info, VestingEscrow>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vesting_escrow,
         associated_token::token_program = token_program,
     )]
-    pub vrgda_sol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub vesting_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        associated_token::mint = mint,
+        associated_token::authority = beneficiary,
+        associated_token::token_program = token_program,
+    )]
+    pub beneficiary_ata: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// CHECK: checked in the constraint has_one
     #[account(
-        address = vrgda.authority,
+        mint::token_program = token_program,
     )]
-    pub authority: UncheckedAccount<'info>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
+}
 
-    pub rent: Sysvar<'info, Rent>,
+#[derive(Accounts)]
+pub struct ClaimReserve<'info> {
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = mint,
+        constraint = reserve_escrow.beneficiary == recipient.key() @ VRGDAError::AddressesDontMatch,
+        seeds = [b"reserve".as_ref(), reserve_escrow.vrgda.as_ref()],
+        bump = reserve_escrow.bump,
+    )]
+    pub reserve_escrow: Box<Account<'info, VestingEscrow>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = reserve_escrow,
+        associated_token::token_program = token_program,
+    )]
+    pub reserve_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program,
+    )]
+    pub recipient_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CloseAuction<'info> {
+pub struct UpdateParams<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayout<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+}
+
+#[derive(Accounts)]
+pub struct SetRateLimits<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+}
+
+#[derive(Accounts)]
+pub struct Migrate<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: older layouts do not deserialize as `VRGDA`, so `migrate` checks
+    /// the discriminator and the authority itself.
+    #[account(
+        mut,
+        owner = crate::ID,
+    )]
+    pub vrgda: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetCommitReveal<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority,
+        has_one = mint,
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: Checked against the mint after deserializing. Only required in
+    /// `MetadataMode::Metaplex`.
+    #[account(mut)]
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: This is the Metaplex Token Metadata program
+    #[account(address = METADATA_PROGRAM_ID)]
+    pub metadata_program: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    pub new_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = mint,
+        constraint = vrgda.pending_authority == Some(new_authority.key()) @ VRGDAError::NotPendingAuthority,
+    )]
+    pub vrgda: Box<Account<'info, state::VRGDA>>,
+
     #[account(
         mut,
-        address = vrgda.authority,
+        mint::token_program = token_program,
     )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: Checked against the mint after deserializing. Only required in
+    /// `MetadataMode::Metaplex`.
+    #[account(mut)]
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: This is the Metaplex Token Metadata program
+    #[account(address = METADATA_PROGRAM_ID)]
+    pub metadata_program: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAuction<'info> {
+    #[account(mut)]
     pub authority: Signer<'info>,
 
     #[account(
         mut,
         close = authority,
-        address = vrgda.mint,
         has_one = authority,
+        has_one = mint,
     )]
     pub vrgda: Box<Account<'info, state::VRGDA>>,
+
     #[account(
         mut,
         associated_token::mint = mint,
@@ -526,13 +4314,25 @@ pub struct CloseAuction<'info> {
         associated_token::token_program = token_program,
     )]
     pub vrgda_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Receives the unsold tokens.
     #[account(
-        mut,
-        associated_token::mint = wsol_mint,
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint,
         associated_token::authority = authority,
         associated_token::token_program = token_program,
     )]
-    pub vrgda_sol_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub authority_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Has to be empty, see `close_auction`. Only required when the auction
+    /// escrows its proceeds.
+    #[account(
+        associated_token::mint = wsol_mint,
+        associated_token::authority = vrgda,
+        associated_token::token_program = token_program,
+    )]
+    pub proceeds_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
 
     #[account(
         mut,
@@ -543,10 +4343,9 @@ pub struct CloseAuction<'info> {
     #[account(
         mint::token_program = token_program,
     )]
-    pub wsol_mint: InterfaceAccount<'info, Mint>,
+    pub wsol_mint: Box<InterfaceAccount<'info, Mint>>,
 
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
 }
@@ -2,6 +2,7 @@
 //! Defines PreciseNumber, a U192 wrapper with float-like operations
 // Stolen from SPL math, but changing inner unit
 
+use crate::error::{VRGDAError, VrgdaResult};
 use crate::math::signed_precise::SignedPreciseNumber;
 use crate::math::uint::U192;
 use anchor_lang::prelude::msg;
@@ -539,8 +540,10 @@ pub fn to_mint_amount(amt: &PreciseNumber, mint_decimals: u8, ceil: bool) -> u64
         .unwrap() as u64
 }
 
-pub fn to_actual_mint_amount(amt: &PreciseNumber) -> u64 {
-    amt.to_imprecise().unwrap() as u64
+pub fn to_actual_mint_amount(amt: &PreciseNumber) -> VrgdaResult<u64> {
+    amt.to_imprecise()
+        .and_then(|amount| u64::try_from(amount).ok())
+        .ok_or(VRGDAError::PriceOverflow)
 }
 
 pub fn calculate_refund(amount: u64, mint: &Mint, sol_amount_precise: PreciseNumber) -> u64 {
@@ -1,21 +1,46 @@
 use super::*;
 use crate::{
     error::VrgdaResult,
-    math::{precise_number::PreciseNumber, InnerUint, SignedPreciseNumber, ONE, ONE_PREC},
+    math::{
+        precise_number::PreciseNumber, to_actual_mint_amount, InnerUint, SignedPreciseNumber, ONE,
+        ONE_PREC,
+    },
 };
+use mpl_token_metadata::{MAX_NAME_LENGTH, MAX_SYMBOL_LENGTH, MAX_URI_LENGTH};
 
 pub const WSOL_MINT: Pubkey = Pubkey::new_from_array([
     5, 75, 241, 90, 194, 246, 107, 215, 197, 77, 10, 129, 16, 97, 158, 122, 32, 92, 138, 255, 155,
     78, 106, 228, 38, 81, 21, 101, 179, 158, 220, 99,
 ]);
 
+/// Maximum number of payees that can split the proceeds of a single auction.
+pub const MAX_PAYEES: usize = 8;
+/// Payee shares are expressed in basis points and must add up to this value.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Layout version written to new and migrated `VRGDA` accounts. Sizes leave
+/// out the 8 byte discriminator.
+///
+/// 0. The first release, `VrgdaV0`, 155 bytes.
+/// 1. Adds the version byte, every field after `bump` and the reserved bytes,
+///    1003 bytes.
+pub const VRGDA_VERSION: u8 = 1;
+/// Zeroed bytes at the end of `VRGDA`. New fields are carved out of them, so
+/// the account size, and every existing auction, stays valid.
+pub const VRGDA_RESERVED_BYTES: usize = 32;
+/// Space of a version 0 account, see `VrgdaV0`.
+pub const VRGDA_V0_SPACE: usize = 155;
+
 #[account]
 #[derive(InitSpace, Debug)]
 pub struct VRGDA {
+    /// Layout version, `VRGDA_VERSION` once created or migrated.
+    pub version: u8,
     pub mint: Pubkey,
     // The max amount that can be minted to a buyer's account.
     pub total_supply: u64,
-    /// The account that receives payments.
+    /// Admin of the auction. Handed over with `propose_authority` and
+    /// `accept_authority`.
     pub authority: Pubkey,
     /// The target price for a token (wad).
     pub target_price: u128, // p0
@@ -37,6 +62,494 @@ pub struct VRGDA {
     pub current_price: u64,
     /// Bump for PDA.
     pub bump: u8,
+    /// Proceeds recipients. When empty `payout` receives every payment.
+    #[max_len(MAX_PAYEES)]
+    pub payees: Vec<Payee>,
+    /// When set, purchased tokens are locked in a per-buyer `VestingEscrow`.
+    pub vesting: Option<VestingConfig>,
+    /// Tokens set aside for the team or treasury. They sit in the reserve escrow
+    /// and never count towards `total_supply` or `tokens_sold`.
+    pub reserve_amount: u64,
+    /// When set, proceeds stay escrowed until the cap is met and buyers can be
+    /// refunded if it is missed.
+    pub soft_cap: Option<SoftCap>,
+    /// Quote tokens collected by the auction so far.
+    pub total_raised: u64,
+    /// Whether the supply is minted upfront or on every purchase.
+    pub mint_mode: MintMode,
+    /// Where the token's name, symbol and uri live.
+    pub metadata_mode: MetadataMode,
+    /// Set once `update_metadata` locks the metadata for good.
+    pub metadata_locked: bool,
+    /// Naming and collection of the NFTs minted by an edition drop.
+    pub edition: Option<EditionConfig>,
+    /// Tells apart auctions of the same mint and authority. Part of the PDA seeds.
+    pub auction_id: u64,
+    /// Authority that created the auction. Only used in the PDA seeds, so the
+    /// address stays the same when `authority` changes.
+    pub creator: Pubkey,
+    /// Proposed new authority, waiting to accept.
+    pub pending_authority: Option<Pubkey>,
+    /// Anti-sniping limits, set with `set_rate_limits`.
+    pub rate_limits: RateLimits,
+    /// Slot of the most recent purchase.
+    pub current_slot: u64,
+    /// Tokens sold in `current_slot`.
+    pub slot_amount: u64,
+    /// When set, the auction only sells through `commit` and `reveal`.
+    pub commit_reveal: Option<CommitRevealConfig>,
+    /// ln(1 − k) as a wad. Stored without its sign, the log is always negative.
+    pub ln_one_minus_k: u128,
+    /// Price ratio between consecutive tokens, q = (1 − k)^(−1/r), as a wad.
+    pub q: u128,
+    /// Set on auctions migrated from version 0. They sit at the address the
+    /// first release derived without `auction_id` and keep signing with it.
+    pub legacy_seeds: bool,
+    /// Receives the proceeds when there are no payees. Set by the authority
+    /// with `set_payout`.
+    pub payout: Pubkey,
+    /// Room for later fields, see `VRGDA_RESERVED_BYTES`.
+    pub reserved: [u8; VRGDA_RESERVED_BYTES],
+}
+
+/// `VRGDA` as the first release laid it out, before accounts were versioned.
+/// Frozen, `migrate` reads the auctions created back then through it.
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Debug, InitSpace)]
+pub struct VrgdaV0 {
+    pub mint: Pubkey,
+    pub total_supply: u64,
+    pub authority: Pubkey,
+    pub target_price: u128,
+    pub decay_constant_percent: u64,
+    pub tokens_sold: u64,
+    pub created_at_timestamp: i64,
+    pub vrgda_start_timestamp: i64,
+    pub buy_window_time: i64,
+    pub last_buy_timestamp: i64,
+    pub auction_ended: bool,
+    pub schedule: Schedule,
+    pub current_price: u64,
+    pub bump: u8,
+}
+
+/// Seeds of an auction's address, see `VRGDA::signer`.
+pub struct VrgdaSigner {
+    mint: Pubkey,
+    creator: Pubkey,
+    auction_id: Option<[u8; 8]>,
+    bump: [u8; 1],
+}
+
+impl VrgdaSigner {
+    pub fn seeds(&self) -> Vec<&[u8]> {
+        let mut seeds = vec![b"vrgda".as_ref(), self.mint.as_ref(), self.creator.as_ref()];
+        if let Some(auction_id) = &self.auction_id {
+            seeds.push(auction_id);
+        }
+        seeds.push(&self.bump);
+        seeds
+    }
+}
+
+/// Arguments of `initialize_vrgda` and `initialize_vrgda_with_mint`.
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Debug, PartialEq)]
+pub struct AuctionParams {
+    /// Tells apart auctions of the same mint and authority.
+    pub auction_id: u64,
+    pub target_price: u128,
+    pub decay_constant_percent: u64,
+    /// Clamped to the current time when in the past.
+    pub vrgda_start_timestamp: i64,
+    pub total_supply: u64,
+    pub r: u64,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub payees: Vec<Payee>,
+    pub vesting: Option<VestingConfig>,
+    pub reserve: Option<ReserveConfig>,
+    pub soft_cap: Option<SoftCap>,
+    pub mint_mode: MintMode,
+    pub metadata_mode: MetadataMode,
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Debug, InitSpace, PartialEq)]
+pub struct Payee {
+    /// Wallet whose quote token account receives this share.
+    pub wallet: Pubkey,
+    /// Share of the proceeds in basis points.
+    pub share_bps: u16,
+}
+
+/// One line of a `buy_batch` call.
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Debug, PartialEq)]
+pub struct BatchPurchase {
+    /// Position of the recipient's token account in `remaining_accounts`.
+    pub recipient_index: u8,
+    pub amount: u64,
+}
+
+/// What `buy` charged for a purchase, or `quote` expects it to charge. Both
+/// return it through return data.
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Debug, PartialEq)]
+pub struct PurchaseReceipt {
+    /// Tokens the recipient receives.
+    pub amount: u64,
+    /// Price credited to the auction.
+    pub price: u64,
+    /// Quote tokens taken from the buyer, the price plus the quote mint's
+    /// transfer fee.
+    pub cost: u64,
+}
+
+/// Purchase a buyer signs off-chain so that a relayer can submit it for them
+/// with `buy_with_intent`.
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Debug, PartialEq)]
+pub struct PurchaseIntent {
+    pub vrgda: Pubkey,
+    pub amount: u64,
+    /// Most the buyer pays for `amount`, in quote tokens.
+    pub max_cost: u64,
+    /// Any value, but each one can only be used once per buyer and auction.
+    pub nonce: u64,
+    /// Unix timestamp after which the intent can no longer be used.
+    pub expiry: i64,
+}
+
+impl PurchaseIntent {
+    /// Bytes the buyer signs, the borsh encoding of the intent.
+    pub fn message(&self) -> Result<Vec<u8>> {
+        let mut message = Vec::new();
+        self.serialize(&mut message)?;
+        Ok(message)
+    }
+}
+
+/// Marks a purchase intent's nonce as used. Its existence is what rejects a
+/// replayed intent.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct IntentNonce {
+    /// Bump for PDA.
+    pub bump: u8,
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Debug, InitSpace, PartialEq)]
+pub struct VestingConfig {
+    /// Seconds after the vesting start before anything unlocks.
+    pub cliff_seconds: i64,
+    /// Seconds after the vesting start at which everything is unlocked.
+    pub duration_seconds: i64,
+}
+
+impl VestingConfig {
+    pub fn validate(&self) -> VrgdaResult<()> {
+        if self.cliff_seconds < 0
+            || self.duration_seconds <= 0
+            || self.cliff_seconds > self.duration_seconds
+        {
+            return Err(VRGDAError::InvalidVestingConfig);
+        }
+        Ok(())
+    }
+
+    /// Amount of `total` unlocked at `now` for a schedule that started at `start`.
+    /// Nothing unlocks before the cliff, then the total unlocks linearly from
+    /// `start` until `start + duration_seconds`.
+    pub fn unlocked_amount(&self, total: u64, start: i64, now: i64) -> VrgdaResult<u64> {
+        let elapsed = now.saturating_sub(start);
+        if elapsed < self.cliff_seconds {
+            return Ok(0);
+        }
+        if elapsed >= self.duration_seconds {
+            return Ok(total);
+        }
+
+        let unlocked = (total as u128)
+            .checked_mul(elapsed as u128)
+            .and_then(|v| v.checked_div(self.duration_seconds as u128))
+            .ok_or(VRGDAError::MathOverflow)?;
+        Ok(unlocked as u64)
+    }
+}
+
+/// Locks tokens for `beneficiary` until they unlock on `config`. Used for the
+/// purchases of vesting auctions and for the reserve allocation.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct VestingEscrow {
+    pub vrgda: Pubkey,
+    pub mint: Pubkey,
+    pub beneficiary: Pubkey,
+    /// Vesting terms copied from the auction on the first deposit.
+    pub config: VestingConfig,
+    /// Total tokens deposited into the escrow.
+    pub total_amount: u64,
+    /// Tokens already released to the beneficiary.
+    pub claimed_amount: u64,
+    /// The vesting schedule runs from here. For purchases this is the average deposit
+    /// time weighted by amount, for the reserve it is the auction start.
+    pub start_timestamp: i64,
+    /// Bump for PDA.
+    pub bump: u8,
+}
+
+impl VestingEscrow {
+    /// Adds `amount` tokens bought at `now`. The start moves towards `now` by the
+    /// share of `amount` in the new total, so a small early deposit can't unlock a
+    /// large later one straight away.
+    pub fn deposit(&mut self, amount: u64, now: i64) -> VrgdaResult<()> {
+        let total_amount = self
+            .total_amount
+            .checked_add(amount)
+            .ok_or(VRGDAError::MathOverflow)?;
+        if self.total_amount == 0 {
+            self.start_timestamp = now;
+        } else {
+            let weighted = (self.start_timestamp as i128)
+                .checked_mul(self.total_amount as i128)
+                .and_then(|v| v.checked_add((now as i128).checked_mul(amount as i128)?))
+                .and_then(|v| v.checked_div(total_amount as i128))
+                .ok_or(VRGDAError::MathOverflow)?;
+            self.start_timestamp = weighted as i64;
+        }
+        self.total_amount = total_amount;
+        Ok(())
+    }
+
+    /// Unlocked tokens at `now` that have not been released yet. A deposit can
+    /// push the start forward past what was already claimed, which counts as 0.
+    pub fn claimable(&self, now: i64) -> VrgdaResult<u64> {
+        let unlocked = self
+            .config
+            .unlocked_amount(self.total_amount, self.start_timestamp, now)?;
+        Ok(unlocked.saturating_sub(self.claimed_amount))
+    }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Debug, InitSpace, PartialEq)]
+pub struct ReserveConfig {
+    /// Tokens minted to the reserve on top of `total_supply`.
+    pub amount: u64,
+    /// Wallet allowed to claim the reserve.
+    pub recipient: Pubkey,
+    /// Unlock schedule, counted from the auction start.
+    pub unlock: VestingConfig,
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Debug, InitSpace, PartialEq)]
+pub struct SoftCap {
+    /// Minimum raise in quote token units.
+    pub amount: u64,
+    /// Unix timestamp by which `amount` has to be raised.
+    pub deadline: i64,
+}
+
+/// Limits against bots sweeping the auction. Unset fields are not enforced.
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Debug, Default, InitSpace, PartialEq)]
+pub struct RateLimits {
+    /// Most tokens a single purchase instruction can buy.
+    pub max_per_transaction: Option<u64>,
+    /// Most tokens sold across all purchases landing in the same slot.
+    pub max_per_slot: Option<u64>,
+    /// Seconds a wallet has to wait between two purchases.
+    pub wallet_cooldown_seconds: Option<i64>,
+}
+
+impl RateLimits {
+    pub fn validate(&self) -> VrgdaResult<()> {
+        if self.max_per_transaction == Some(0)
+            || self.max_per_slot == Some(0)
+            || matches!(self.wallet_cooldown_seconds, Some(cooldown) if cooldown <= 0)
+        {
+            return Err(VRGDAError::InvalidRateLimits);
+        }
+        Ok(())
+    }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Debug, InitSpace, PartialEq)]
+pub struct CommitRevealConfig {
+    /// Slots a commit has to wait before it can be revealed.
+    pub min_delay_slots: u64,
+    /// Slots after the commit at which it can no longer be revealed, only
+    /// reclaimed.
+    pub reveal_timeout_slots: u64,
+}
+
+impl CommitRevealConfig {
+    pub fn validate(&self) -> VrgdaResult<()> {
+        if self.min_delay_slots == 0 || self.reveal_timeout_slots <= self.min_delay_slots {
+            return Err(VRGDAError::InvalidCommitRevealConfig);
+        }
+        Ok(())
+    }
+}
+
+/// Hidden purchase of a commit-reveal auction, with its quote funds escrowed in
+/// the commit's own token account.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct PurchaseCommit {
+    pub vrgda: Pubkey,
+    pub buyer: Pubkey,
+    /// `PurchaseCommit::hash` of the amount, max cost and salt.
+    pub commitment: [u8; 32],
+    /// First slot at which the commit can be revealed.
+    pub reveal_slot: u64,
+    /// Slot from which the commit can only be reclaimed.
+    pub expiry_slot: u64,
+    /// Bump for PDA.
+    pub bump: u8,
+}
+
+impl PurchaseCommit {
+    /// Commitment to buying `amount` for at most `max_cost` quote tokens.
+    pub fn hash(amount: u64, max_cost: u64, salt: &[u8; 32]) -> [u8; 32] {
+        anchor_lang::solana_program::hash::hashv(&[
+            &amount.to_le_bytes(),
+            &max_cost.to_le_bytes(),
+            salt,
+        ])
+        .to_bytes()
+    }
+}
+
+/// Per-buyer purchase history of an auction.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct BuyerRecord {
+    pub vrgda: Pubkey,
+    /// Wallet that received the purchases, which is not necessarily the one that paid.
+    pub buyer: Pubkey,
+    /// Quote tokens paid across all purchases.
+    pub quote_spent: u64,
+    /// Sale tokens received across all purchases.
+    pub tokens_bought: u64,
+    /// Bump for PDA.
+    pub bump: u8,
+    /// Time of the latest purchase, for the wallet cooldown.
+    pub last_purchase_timestamp: i64,
+}
+
+/// Standing order to buy `amount` once the price drops to `max_price`. The
+/// quote funds sit in the order's own token account until it is executed or
+/// cancelled.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct LimitOrder {
+    pub vrgda: Pubkey,
+    /// Wallet that placed the order and receives the tokens.
+    pub owner: Pubkey,
+    /// Lets one owner keep several orders open on the same auction.
+    pub order_id: u64,
+    /// Sale tokens to buy.
+    pub amount: u64,
+    /// Most the owner pays for the whole `amount`, in quote tokens.
+    pub max_price: u64,
+    /// Quote tokens paid to whoever executes the order.
+    pub tip: u64,
+    /// Bump for PDA.
+    pub bump: u8,
+}
+
+/// Recurring buy of `amount_per_slice` every `interval_seconds`, paid from quote
+/// funds escrowed in the order's own token account.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct DcaOrder {
+    pub vrgda: Pubkey,
+    /// Wallet that created the order and receives the tokens.
+    pub owner: Pubkey,
+    /// Lets one owner keep several orders open on the same auction.
+    pub order_id: u64,
+    /// Sale tokens bought per slice.
+    pub amount_per_slice: u64,
+    /// Most a single slice may cost, in quote tokens.
+    pub max_price_per_slice: u64,
+    /// Seconds between slices.
+    pub interval_seconds: i64,
+    /// Slices left to buy. The order closes after the last one.
+    pub slices_remaining: u64,
+    /// Earliest time the next slice can be executed.
+    pub next_slice_timestamp: i64,
+    /// Bump for PDA.
+    pub bump: u8,
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Debug, InitSpace, PartialEq)]
+pub enum MintMode {
+    /// `total_supply` is minted into the vault at initialize and sold from there.
+    PreMint,
+    /// The VRGDA keeps mint authority and mints each purchase, capped at `total_supply`.
+    Lazy,
+    /// Like `Lazy` but without a cap, emitting forever along the linear schedule.
+    Perpetual,
+    /// Every purchase mints a new 1-of-1 NFT through `buy_edition`. `total_supply`
+    /// and `tokens_sold` count items rather than base units.
+    Editions,
+}
+
+/// Arguments of `initialize_edition_drop`.
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Debug, PartialEq)]
+pub struct EditionDropParams {
+    /// Tells apart auctions of the same mint and authority.
+    pub auction_id: u64,
+    pub target_price: u128,
+    pub decay_constant_percent: u64,
+    /// Clamped to the current time when in the past.
+    pub vrgda_start_timestamp: i64,
+    /// Most editions the drop sells.
+    pub max_editions: u64,
+    pub r: u64,
+    pub payees: Vec<Payee>,
+    pub edition: EditionConfig,
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Debug, InitSpace, PartialEq)]
+pub struct EditionConfig {
+    /// Base name, the NFTs are called `"{name} #{n}"`.
+    #[max_len(MAX_NAME_LENGTH)]
+    pub name: String,
+    #[max_len(MAX_SYMBOL_LENGTH)]
+    pub symbol: String,
+    /// Base uri, the NFTs point at `"{uri}{n}.json"`.
+    #[max_len(MAX_URI_LENGTH)]
+    pub uri: String,
+    /// Sized Metaplex collection every NFT is verified into. The VRGDA has to be
+    /// its update authority or an approved collection authority.
+    pub collection: Option<Pubkey>,
+}
+
+impl EditionConfig {
+    /// Checks that the suffixed name and uri of the last edition still fit Metaplex's limits.
+    pub fn validate(&self, max_editions: u64) -> VrgdaResult<()> {
+        let digits = max_editions.to_string().len();
+        if max_editions == 0
+            || self.name.len() + " #".len() + digits > MAX_NAME_LENGTH
+            || self.symbol.len() > MAX_SYMBOL_LENGTH
+            || self.uri.len() + digits + ".json".len() > MAX_URI_LENGTH
+        {
+            return Err(VRGDAError::InvalidEditionConfig);
+        }
+        Ok(())
+    }
+
+    pub fn edition_name(&self, number: u64) -> String {
+        format!("{} #{}", self.name, number)
+    }
+
+    pub fn edition_uri(&self, number: u64) -> String {
+        format!("{}{}.json", self.uri, number)
+    }
+}
+
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Debug, InitSpace, PartialEq)]
+pub enum MetadataMode {
+    /// A Metaplex metadata account created through `create_metadata_accounts_v3`.
+    Metaplex,
+    /// Token-2022 token-metadata extension stored on the mint itself.
+    Token2022,
 }
 
 #[derive(AnchorDeserialize, AnchorSerialize, Clone, Debug, InitSpace)]
@@ -71,6 +584,7 @@ impl VRGDA {
         bump: u8,
     ) -> Self {
         Self {
+            version: VRGDA_VERSION,
             mint,
             total_supply: 0,
             authority,
@@ -85,8 +599,290 @@ impl VRGDA {
             schedule,
             current_price: 0,
             bump,
+            payees: Vec::new(),
+            vesting: None,
+            reserve_amount: 0,
+            soft_cap: None,
+            total_raised: 0,
+            mint_mode: MintMode::PreMint,
+            metadata_mode: MetadataMode::Metaplex,
+            metadata_locked: false,
+            edition: None,
+            auction_id: 0,
+            creator: authority,
+            pending_authority: None,
+            rate_limits: RateLimits::default(),
+            current_slot: 0,
+            slot_amount: 0,
+            commit_reveal: None,
+            ln_one_minus_k: 0,
+            q: 0,
+            legacy_seeds: false,
+            payout: authority,
+            reserved: [0; VRGDA_RESERVED_BYTES],
+        }
+    }
+
+    /// An auction created by the first release, in the current layout but still
+    /// at version 0. Fields it did not have start out at their defaults.
+    pub fn from_v0(v0: VrgdaV0) -> Self {
+        Self {
+            version: 0,
+            total_supply: v0.total_supply,
+            tokens_sold: v0.tokens_sold,
+            buy_window_time: v0.buy_window_time,
+            last_buy_timestamp: v0.last_buy_timestamp,
+            auction_ended: v0.auction_ended,
+            current_price: v0.current_price,
+            legacy_seeds: true,
+            ..Self::new(
+                v0.mint,
+                v0.authority,
+                v0.target_price,
+                v0.decay_constant_percent,
+                v0.schedule,
+                v0.created_at_timestamp,
+                v0.vrgda_start_timestamp,
+                v0.bump,
+            )
         }
     }
+
+    /// Seeds to sign for the auction with.
+    pub fn signer(&self) -> VrgdaSigner {
+        VrgdaSigner {
+            mint: self.mint,
+            creator: self.creator,
+            auction_id: (!self.legacy_seeds).then(|| self.auction_id.to_le_bytes()),
+            bump: [self.bump],
+        }
+    }
+
+    /// Works out `ln_one_minus_k` and `q` from the decay constant and schedule.
+    /// Has to run whenever either changes, buys only read the results.
+    pub fn update_pricing_constants(&mut self) -> VrgdaResult<()> {
+        // decay fraction k = (decay_constant_percent / 100) in wad
+        let k_wad = PreciseNumber::new(self.decay_constant_percent as u128)
+            .ok_or(VRGDAError::MathOverflow)?
+            .checked_div(&PreciseNumber::new(100).ok_or(VRGDAError::MathOverflow)?)
+            .ok_or(VRGDAError::DivisionError)?;
+        let one_minus_k = ONE_PREC
+            .checked_sub(&k_wad)
+            .ok_or(VRGDAError::OneMinusKError)?;
+
+        // ln(1−k) — must be negative
+        let ln1k = one_minus_k.log().ok_or(VRGDAError::LogError)?;
+        if !ln1k.is_negative {
+            return Err(VRGDAError::LogError);
+        }
+
+        // geometric ratio q = (1−k)^(−1/r) = exp(−ln(1−k) / r)
+        let inv_r = PreciseNumber::one()
+            .checked_div(
+                &PreciseNumber::new(self.schedule.get_r() as u128)
+                    .ok_or(VRGDAError::MathOverflow)?,
+            )
+            .ok_or(VRGDAError::InvalidSchedule)?
+            .signed();
+        let q = ln1k
+            .negate()
+            .checked_mul(&inv_r)
+            .ok_or(VRGDAError::ExponentError)?
+            .exp()
+            .ok_or(VRGDAError::ExponentTooLarge)?;
+
+        self.ln_one_minus_k =
+            u128::try_from(ln1k.value.value).map_err(|_| VRGDAError::MathOverflow)?;
+        self.q = u128::try_from(q.value).map_err(|_| VRGDAError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Base units that make up one unit on the curve. Fungible sales are priced
+    /// per whole 6-decimal token, edition drops per item.
+    pub fn unit_size(&self) -> u64 {
+        match self.mint_mode {
+            MintMode::Editions => 1,
+            _ => 1_000_000,
+        }
+    }
+
+    /// True when purchases are limited by `total_supply`.
+    pub fn is_capped(&self) -> bool {
+        self.mint_mode != MintMode::Perpetual
+    }
+
+    /// True when purchases have to go through `buy`, because the auction keeps
+    /// refunds, vesting or cooldowns in accounts created per buyer.
+    pub fn needs_buyer_accounts(&self) -> bool {
+        self.soft_cap.is_some()
+            || self.vesting.is_some()
+            || self.rate_limits.wallet_cooldown_seconds.is_some()
+    }
+
+    /// Applies the per-purchase and per-slot limits to a purchase of `amount`
+    /// and counts it towards the slot.
+    pub fn record_rate_limited_purchase(&mut self, amount: u64, slot: u64) -> VrgdaResult<()> {
+        if let Some(max) = self.rate_limits.max_per_transaction {
+            if amount > max {
+                return Err(VRGDAError::TransactionLimitExceeded);
+            }
+        }
+
+        if slot != self.current_slot {
+            self.current_slot = slot;
+            self.slot_amount = 0;
+        }
+        self.slot_amount = self
+            .slot_amount
+            .checked_add(amount)
+            .ok_or(VRGDAError::MathOverflow)?;
+        if let Some(max) = self.rate_limits.max_per_slot {
+            if self.slot_amount > max {
+                return Err(VRGDAError::SlotLimitExceeded);
+            }
+        }
+        Ok(())
+    }
+
+    /// True when payments collect in the proceeds vault until `distribute`, as
+    /// they do with payees or a soft cap. Otherwise they go straight to `payout`.
+    pub fn escrows_proceeds(&self) -> bool {
+        !self.payees.is_empty() || self.soft_cap.is_some()
+    }
+
+    /// True when there is no soft cap or it has been reached.
+    pub fn soft_cap_met(&self) -> bool {
+        match &self.soft_cap {
+            Some(soft_cap) => self.total_raised >= soft_cap.amount,
+            None => true,
+        }
+    }
+
+    /// True once the soft cap deadline has passed without reaching the cap.
+    pub fn soft_cap_failed(&self, now: i64) -> bool {
+        match &self.soft_cap {
+            Some(soft_cap) => now > soft_cap.deadline && !self.soft_cap_met(),
+            None => false,
+        }
+    }
+
+    /// Checks that the payee list fits on the account and that the shares
+    /// add up to exactly `BPS_DENOMINATOR`.
+    pub fn validate_payees(payees: &[Payee]) -> VrgdaResult<()> {
+        if payees.len() > MAX_PAYEES {
+            return Err(VRGDAError::TooManyPayees);
+        }
+        if payees.is_empty() {
+            return Ok(());
+        }
+
+        let mut total_bps = 0u64;
+        for (i, payee) in payees.iter().enumerate() {
+            if payee.share_bps == 0 {
+                return Err(VRGDAError::InvalidPayeeShares);
+            }
+            if payees[..i].iter().any(|p| p.wallet == payee.wallet) {
+                return Err(VRGDAError::DuplicatePayee);
+            }
+            total_bps += payee.share_bps as u64;
+        }
+        if total_bps != BPS_DENOMINATOR {
+            return Err(VRGDAError::InvalidPayeeShares);
+        }
+        Ok(())
+    }
+
+    /// Price of the next `amount` tokens at `now`. A purchase after the buy
+    /// window has passed without sales pays the last `current_price`.
+    pub fn sale_price(&self, now: i64, amount: u64) -> VrgdaResult<u64> {
+        let sold = self.tokens_sold;
+        let time_since_last_buy = if sold == 0 {
+            0
+        } else {
+            now.checked_sub(self.last_buy_timestamp).unwrap_or(0)
+        };
+
+        trace!("Now: {}", now);
+        trace!("Tokens sold before purchase: {}", sold);
+        trace!("R val: {:?}", self.schedule);
+
+        let price = to_actual_mint_amount(&self.vrgda_price_for_amount(now, sold, amount)?)?;
+        trace!("Price in SOL: {:?}", price);
+
+        trace!("Time since last buy: {} seconds", time_since_last_buy);
+
+        trace!("Buy window time: {} seconds", self.buy_window_time);
+
+        if time_since_last_buy > self.buy_window_time {
+            return Ok(self.current_price);
+        }
+        Ok(price)
+    }
+
+    /// Checks the pricing parameters: a target price of at least one whole
+    /// lamport, a decay strictly between 0 and 100 percent and a non-zero rate.
+    pub fn validate_params(
+        target_price: u128,
+        decay_constant_percent: u64,
+        r: u64,
+    ) -> VrgdaResult<()> {
+        if target_price < ONE {
+            return Err(VRGDAError::InvalidTargetPrice);
+        }
+        // ln(1 - k) has to be negative for the price to decay.
+        if decay_constant_percent == 0 {
+            return Err(VRGDAError::NonNegativeDecayConstant);
+        }
+        if decay_constant_percent >= 100 {
+            return Err(VRGDAError::InvalidDecayConstant);
+        }
+        if r == 0 {
+            return Err(VRGDAError::InvalidSchedule);
+        }
+        Ok(())
+    }
+
+    /// Checks that the token metadata fits Metaplex's limits.
+    pub fn validate_metadata(name: &str, symbol: &str, uri: &str) -> VrgdaResult<()> {
+        if name.len() > MAX_NAME_LENGTH
+            || symbol.len() > MAX_SYMBOL_LENGTH
+            || uri.len() > MAX_URI_LENGTH
+        {
+            return Err(VRGDAError::MetadataTooLong);
+        }
+        Ok(())
+    }
+
+    /// Splits `total` between the payees in order. Every share is rounded
+    /// down and the leftover dust goes to the first payee, so the parts
+    /// always add up to `total`.
+    pub fn split_proceeds(&self, total: u64) -> VrgdaResult<Vec<u64>> {
+        // Without payees `payout` is the sole recipient.
+        if self.payees.is_empty() {
+            return Ok(vec![total]);
+        }
+        let mut parts = Vec::with_capacity(self.payees.len());
+        let mut distributed = 0u64;
+        for payee in self.payees.iter() {
+            let part = (total as u128)
+                .checked_mul(payee.share_bps as u128)
+                .and_then(|v| v.checked_div(BPS_DENOMINATOR as u128))
+                .ok_or(VRGDAError::MathOverflow)? as u64;
+            distributed = distributed
+                .checked_add(part)
+                .ok_or(VRGDAError::MathOverflow)?;
+            parts.push(part);
+        }
+
+        let dust = total
+            .checked_sub(distributed)
+            .ok_or(VRGDAError::MathOverflow)?;
+        if let Some(first) = parts.first_mut() {
+            *first = first.checked_add(dust).ok_or(VRGDAError::MathOverflow)?;
+        }
+        Ok(parts)
+    }
+
     // pub fn get_vrgda_price<T: VrgdaSchedule>(
     //     &self,
     //     time_since_start: Wad,
@@ -106,150 +902,124 @@ impl VRGDA {
     //     // Final price: target_price * multiplier.
     //     wad_mul(self.target_price.cast().unwrap(), multiplier)
     // }
-    fn get_target_sale_time(&self, n: u64) -> u64 {
+    fn get_target_sale_time(&self, n: u64) -> VrgdaResult<u64> {
         // For a linear schedule t* = n / r
         // 1) build n_wad = n * 10^18
 
-        msg!("N: {:?}", n);
+        trace!("N: {:?}", n);
         // 2) build r_wad = r * 10^18
         let r = match self.schedule {
-            Schedule::LinearSchedule { r } => r,
-            _ => unreachable!(),
+            Schedule::LinearSchedule { r } if r > 0 => r,
+            _ => return Err(VRGDAError::InvalidSchedule),
         };
-        msg!("R: {:?}", r);
-        let n_over_r = (n + r).checked_div(r).unwrap();
-        n_over_r
+        trace!("R: {:?}", r);
+        let n_over_r = n
+            .checked_add(r)
+            .ok_or(VRGDAError::MathOverflow)?
+            .checked_div(r)
+            .ok_or(VRGDAError::InvalidSchedule)?;
+        Ok(n_over_r)
         // .checked_mul(&ONE_PREC)
         // .unwrap()
     }
 
-    fn get_target_sale_time_precise(&self, n: u64) -> PreciseNumber {
+    fn get_target_sale_time_precise(&self, n: u64) -> VrgdaResult<PreciseNumber> {
         // For a linear schedule t* = n / r
         // 1) build n_wad = n * 10^18
 
         let n = PreciseNumber {
             value: InnerUint::from(n as u128),
         };
-        msg!("N: {:?}", n);
+        trace!("N: {:?}", n);
         // 2) build r_wad = r * 10^18
         let r = match self.schedule {
-            Schedule::LinearSchedule { r } => r,
-            _ => unreachable!(),
+            Schedule::LinearSchedule { r } if r > 0 => r,
+            _ => return Err(VRGDAError::InvalidSchedule),
         };
 
         let r_precise = PreciseNumber {
             value: InnerUint::from(r as u128),
         };
-        msg!("R: {:?}", r_precise);
-        let n_over_r = n.checked_div(&r_precise).unwrap();
-        msg!("N OVER R: {:?}", n_over_r);
+        trace!("R: {:?}", r_precise);
+        let n_over_r = n
+            .checked_div(&r_precise)
+            .ok_or(VRGDAError::InvalidSchedule)?;
+        trace!("N OVER R: {:?}", n_over_r);
 
-        n_over_r
+        Ok(n_over_r)
     }
 
+    /// Price of the next token after `sold`, recomputing `ln(1 − k)` instead of
+    /// reading the stored constants.
     pub fn vrgda_price(&self, now: i64, sold: u64) -> VrgdaResult<PreciseNumber> {
-        // Compute time elapsed since auction start (t - t₀) and scale it (wad).
+        // Compute time elapsed since auction start (t - t₀).
+        let elapsed_seconds = now
+            .checked_sub(self.vrgda_start_timestamp)
+            .filter(|elapsed| *elapsed >= 0)
+            .ok_or(VRGDAError::AuctionNotStarted)?;
         let time_since_start =
-            PreciseNumber::new((now - self.vrgda_start_timestamp) as u128).unwrap();
-        // .checked_mul(&ONE_PREC)
-        // .unwrap();
-        msg!("TIME SINCE START: {:?}", time_since_start);
+            PreciseNumber::new(elapsed_seconds as u128).ok_or(VRGDAError::MathOverflow)?;
+        trace!("TIME SINCE START: {:?}", time_since_start);
 
         // f⁻¹(n) for the (sold + 1)th token.
-        let f_inv = self.get_target_sale_time(sold);
-        msg!("F INVERSE (TIME): {:?}", f_inv);
+        let f_inv = self.get_target_sale_time(sold)?;
+        trace!("F INVERSE (TIME): {:?}", f_inv);
 
-        let target_time = PreciseNumber::new(f_inv as u128).unwrap();
-        msg!("TARGET TIME: {:?}", target_time);
+        let target_time = PreciseNumber::new(f_inv as u128).ok_or(VRGDAError::MathOverflow)?;
+        trace!("TARGET TIME: {:?}", target_time);
         // Compute the deviation: (t - t₀) - f⁻¹(n)
-        let exponent_input = 
-        
-        // if f_inv.value > time_since_start.value {
-        //     // If f_inv is larger, log detailed information for debugging
-        //     msg!("F INVERSE: {:?}", f_inv);
-        //     msg!("TIME SINCE START: {:?}", time_since_start);
-        //     msg!("F INV VALUE: {}", f_inv.value);
-        //     msg!("TIME SINCE START VALUE: {}", time_since_start.value);
-            
-        //     return Err(VRGDAError::ExponentErrorInTMinusFInverse);
-        // } else {
-            
-        time_since_start
+        let exponent_input = time_since_start
             .signed()
             .checked_sub(&target_time.signed())
             .ok_or(VRGDAError::ExponentErrorInTMinusFInverse)?;
-        // };
 
-        let normalized_exponent_input = exponent_input.checked_div(&ONE_PREC.signed()).unwrap();
-        msg!(
+        let normalized_exponent_input = exponent_input
+            .checked_div(&ONE_PREC.signed())
+            .ok_or(VRGDAError::DivisionError)?;
+        trace!(
             "Normalized T MINUS F INVERSE: {:?}",
             normalized_exponent_input
         );
 
         // Convert the decay percentage (for example, if it’s 50, we want 0.50) into wad units.
-        // Here we assume ONE_PREC is your wad constant (e.g. 1e18).
         let decay_fraction = PreciseNumber::new(self.decay_constant_percent as u128)
-            .unwrap()
-            .checked_mul(&ONE_PREC)
-            .unwrap()
-            .checked_div(&PreciseNumber::new(100).unwrap())
-            .unwrap();
+            .and_then(|k| k.checked_mul(&ONE_PREC))
+            .and_then(|k| k.checked_div(&PreciseNumber::new(100)?))
+            .ok_or(VRGDAError::MathOverflow)?;
+        trace!("DECAY FRACTION (wad): {:?}", decay_fraction);
 
-        msg!("DECAY FRACTION (wad): {:?}", decay_fraction);
         // Compute 1 - k in wad form.
-        let one = PreciseNumber::new(ONE).unwrap();
-        msg!("ONE (wad): {:?}", one);
-
+        let one = PreciseNumber::new(ONE).ok_or(VRGDAError::MathOverflow)?;
         let one_minus_k = one
             .checked_sub(&decay_fraction)
             .ok_or(VRGDAError::OneMinusKError)?;
-        msg!("ONE MINUS K (wad): {:?}", one_minus_k);
+        trace!("ONE MINUS K (wad): {:?}", one_minus_k);
 
-        // *** FIX: Normalize one_minus_k by dividing by ONE_PREC so that the log function gets a number < 1.
+        // Normalize one_minus_k by dividing by ONE so that the log function gets a number < 1.
         let normalized_one_minus_k = one_minus_k
-            .checked_div(&PreciseNumber::new(ONE).unwrap())
-            .unwrap();
-        msg!("Normalized ONE MINUS K: {:?}", normalized_one_minus_k);
+            .checked_div(&one)
+            .ok_or(VRGDAError::DivisionError)?;
 
-        // Compute ln(1 - k) on the normalized value. ln(normalized_one_minus_k)
-        // should be negative (e.g. ln(0.5) ≈ -0.693...).
+        // ln(normalized_one_minus_k) should be negative (e.g. ln(0.5) ≈ -0.693...).
         let ln_one_minus_k = normalized_one_minus_k.log().ok_or(VRGDAError::LogError)?;
-        msg!("ln(ONE MINUS K): {:?}", ln_one_minus_k);
-
-        // let exponent_input_signed = exponent_input.signed();
+        trace!("ln(ONE MINUS K): {:?}", ln_one_minus_k);
 
-        // let normalized_exponent_input_signed = normalized_exponent_input.signed();
         // Multiply to get the raw exponent: (t - t₀ - f⁻¹(n)) * ln(1 - k)
+        let max_exponent = PreciseNumber::new(u64::MAX as u128).ok_or(VRGDAError::MathOverflow)?;
         let raw_exponent = normalized_exponent_input
             .checked_mul(&ln_one_minus_k)
-            .and_then(|result| {
-                // More explicit bounds checking
-                if result.value > PreciseNumber::new(u64::MAX as u128).unwrap()
-                    || result.value < PreciseNumber::new(0).unwrap()
-                {
-                    msg!("Exponent out of bounds: {:?}", result);
-                    None
-                } else {
-                    Some(result)
-                }
-            })
+            .filter(|result| result.value <= max_exponent)
             .ok_or(VRGDAError::ExponentError)?;
-        msg!("RAW EXPONENT (pre-scale): {:?}", raw_exponent);
-
-        // Since time_since_start was scaled by ONE_PREC and we normalized ln(1-k),
-        // the product is off by ONE_PREC. Downscale by dividing by ONE_PREC.
-        // let exponent = raw_exponent
-        //     .checked_div(&PreciseNumber::new(ONE).unwrap().signed())
-        //     .ok_or(VRGDAError::DivisionError)?;
-        // msg!("SCALED EXPONENT: {:?}", exponent);
+        trace!("RAW EXPONENT (pre-scale): {:?}", raw_exponent);
 
         // Compute the multiplier: e^(exponent)
         let multiplier = raw_exponent.exp().ok_or(VRGDAError::ExponentTooLarge)?;
-        msg!("MULTIPLIER: {:?}", multiplier);
+        trace!("MULTIPLIER: {:?}", multiplier);
 
         // Multiply the target price (converted to a PreciseNumber) by the multiplier.
-        let target_price_scaled = PreciseNumber::new(self.target_price as u128).unwrap();
-        Ok(target_price_scaled.checked_mul(&multiplier).unwrap())
+        PreciseNumber::new(self.target_price)
+            .and_then(|target_price| target_price.checked_mul(&multiplier))
+            .ok_or(VRGDAError::PriceOverflow)
     }
 
     /// O(1) closed-form cost for buying `amount` tokens starting from `sold`
@@ -259,64 +1029,71 @@ impl VRGDA {
         sold: u64,
         amount: u64,
     ) -> VrgdaResult<PreciseNumber> {
+        // Older layouts lack the precomputed constants.
+        if self.version != VRGDA_VERSION {
+            return Err(VRGDAError::AccountNotMigrated);
+        }
         // elapsed seconds since start, then into wad
-        msg!("now: {:?}", now);
-        msg!("start timestamp: {:?}", self.vrgda_start_timestamp);
-        let elapsed_wad = PreciseNumber::new((now - self.vrgda_start_timestamp) as u128)
-            .ok_or(VRGDAError::MathOverflow)?;
-
-        msg!(
-            "ELAPSED TIME in seconds wad: {:?}",
-            elapsed_wad.checked_div(&ONE_PREC).unwrap()
-        );
-
-        let seconds_per_minute = PreciseNumber::new(60u128).unwrap();
-        let elapsed_minutes = elapsed_wad.checked_div(&seconds_per_minute).unwrap();
-        let scaled_sold = sold.checked_div(1_000_000).unwrap();
+        trace!("now: {:?}", now);
+        trace!("start timestamp: {:?}", self.vrgda_start_timestamp);
+        let elapsed_seconds = now
+            .checked_sub(self.vrgda_start_timestamp)
+            .filter(|elapsed| *elapsed >= 0)
+            .ok_or(VRGDAError::AuctionNotStarted)? as u64;
+        let elapsed_wad =
+            PreciseNumber::new(elapsed_seconds as u128).ok_or(VRGDAError::MathOverflow)?;
+
+        trace!("ELAPSED TIME in seconds: {:?}", elapsed_seconds);
+
+        let seconds_per_minute = PreciseNumber::new(60u128).ok_or(VRGDAError::MathOverflow)?;
+        let elapsed_minutes = elapsed_wad
+            .checked_div(&seconds_per_minute)
+            .ok_or(VRGDAError::DivisionError)?;
+        let scaled_sold = sold / self.unit_size();
 
-        msg!("SCALED SOLD: {:?}", scaled_sold);
+        trace!("SCALED SOLD: {:?}", scaled_sold);
 
         let r = match self.schedule {
-            Schedule::LinearSchedule { r } => r,
-            _ => unreachable!(),
+            Schedule::LinearSchedule { r } if r > 0 => r,
+            _ => return Err(VRGDAError::InvalidSchedule),
         };
 
-        let rt = ((now - self.vrgda_start_timestamp) as u64).saturating_mul(r);
-        msg!("RT (ideal tokens to be sold): {:?}", rt);
+        let rt = elapsed_seconds.saturating_mul(r);
+        trace!("RT (ideal tokens to be sold): {:?}", rt);
 
         let precise_rt = PreciseNumber {
             value: InnerUint::from(rt as u128),
         }
         .signed();
 
-        msg!("RT PRECISE: {:?}", precise_rt);
+        trace!("RT PRECISE: {:?}", precise_rt);
         let precise_n = PreciseNumber {
             value: InnerUint::from(scaled_sold as u128),
         }
         .signed();
 
-        msg!("N PRECISE: {:?}", precise_n);
+        trace!("N PRECISE: {:?}", precise_n);
         let precise_rt_minus_n = precise_rt
             .checked_sub(&precise_n)
             .ok_or(VRGDAError::MathOverflow)?;
 
-        msg!(
+        trace!(
             "RT MINUS N PRECISE (ideal tokens to be sold): {:?}",
             precise_rt_minus_n
         );
         let rt_minus_n = precise_rt_minus_n.value.value.as_u64();
 
-        msg!("RT MINUS N (token difference to be sold): {:?}", rt_minus_n);
+        trace!("RT MINUS N (token difference to be sold): {:?}", rt_minus_n);
         // target time for the very next token (sold+1), in wad
 
-        let amount_scaled = amount.checked_div(1_000_000).unwrap();
+        let amount_scaled = amount / self.unit_size();
 
-        msg!("AMOUNT TO BE BOUGHT SCALED: {:?}", amount_scaled);
+        trace!("AMOUNT TO BE BOUGHT SCALED: {:?}", amount_scaled);
         let f_inv_wad = if sold == 0 {
             let n = amount_scaled.min(rt);
-            self.get_target_sale_time_precise(n) // clamp to “one‐second’s worth” of tokens
+            self.get_target_sale_time_precise(n)? // clamp to “one‐second’s worth” of tokens
         } else {
-            self.get_target_sale_time_precise(scaled_sold + 1) // the usual “next token‐index”
+            self.get_target_sale_time_precise(scaled_sold + 1)? // the usual “next token‐index”
         };
 
         // if sold == 0 && amount < self.schedule.get_r() {
@@ -329,243 +1106,79 @@ impl VRGDA {
         // msg!("SOLD IS NOT ZERO");
         // self.get_target_sale_time_precise(scaled_sold + 1)
         // };
-        msg!("f_inv_wad: {:?}", f_inv_wad);
+        trace!("f_inv_wad: {:?}", f_inv_wad);
 
         let t_minus_sr = elapsed_minutes
             .signed()
             .checked_sub(&f_inv_wad.signed())
-            .unwrap()
+            .ok_or(VRGDAError::ExponentErrorInTMinusFInverse)?
             .checked_div(&ONE_PREC.signed())
             .ok_or(VRGDAError::DivisionError)?;
-        msg!("t_minus_sr: {:?}", t_minus_sr);
-
-        // decay fraction k = (decay_constant_percent / 100) in wad
-        let k_wad = PreciseNumber::new(self.decay_constant_percent as u128)
-            .unwrap()
-            // .checked_mul(&ONE_PREC).unwrap()
-            .checked_div(&PreciseNumber::new(100).unwrap())
-            .unwrap();
-        msg!("k_wad: {:?}", k_wad);
-
-        // one_minus_k = 1·10¹⁸ − k_wad, then normalized back to [0,1)
-        let one_minus_k = ONE_PREC
-            .checked_sub(&k_wad)
-            .ok_or(VRGDAError::OneMinusKError)?;
-        msg!("one_minus_k: {:?}", one_minus_k);
-
-        // ln(1−k) — must be negative
-        let ln1k = one_minus_k.log().ok_or(VRGDAError::LogError)?;
-        if !ln1k.is_negative {
-            return Err(VRGDAError::LogError);
-        }
-
-        msg!("ln1k: {:?}", ln1k);
+        trace!("t_minus_sr: {:?}", t_minus_sr);
+
+        // ln(1−k) and q = (1−k)^(−1/r) only depend on the parameters, so they
+        // are worked out by `update_pricing_constants` rather than on every buy.
+        let ln1k = SignedPreciseNumber {
+            value: PreciseNumber {
+                value: InnerUint::from(self.ln_one_minus_k),
+            },
+            is_negative: true,
+        };
+        trace!("ln1k: {:?}", ln1k);
 
         // price of the very next token: p₀·exp( ln(1−k)·(t − S/r) )
         let p0 = PreciseNumber {
             value: InnerUint::from(self.target_price as u128),
         };
 
-        msg!("p0: {:?}", p0);
+        trace!("p0: {:?}", p0);
 
         let raw_exp = ln1k
             .checked_mul(&t_minus_sr)
             .ok_or(VRGDAError::ExponentError)?;
 
-        msg!("RAW EXPONENT: {:?}", raw_exp);
+        trace!("RAW EXPONENT: {:?}", raw_exp);
 
         let next_mul = raw_exp.exp().ok_or(VRGDAError::ExponentTooLarge)?;
 
-        msg!("next_mul: {:?}", next_mul);
+        trace!("next_mul: {:?}", next_mul);
 
-        let p_s1 = p0.checked_mul(&next_mul).unwrap();
-        msg!("p_s1: {:?}", p_s1);
+        let p_s1 = p0.checked_mul(&next_mul).ok_or(VRGDAError::PriceOverflow)?;
+        trace!("p_s1: {:?}", p_s1);
 
-        // geometric ratio q = (1−k)^(−1/r)
-        let inv_r = PreciseNumber::one()
-            .checked_div(&PreciseNumber::new(r as u128).unwrap())
-            .unwrap()
-            .signed();
-
-        msg!("inv_r: {:?}", inv_r);
-        let neg_ln1k = SignedPreciseNumber {
-            value: ln1k.value.clone(),
-            is_negative: !ln1k.is_negative,
+        let q = PreciseNumber {
+            value: InnerUint::from(self.q),
         };
+        trace!("q: {:?}", q);
 
-        msg!("neg_ln1k: {:?}", neg_ln1k);
-
-        let q = neg_ln1k
-            .checked_mul(&inv_r)
-            .ok_or(VRGDAError::ExponentError)?
-            .exp()
-            .ok_or(VRGDAError::ExponentTooLarge)?;
-        msg!("q: {:?}", q);
-
-        let amount_precise = PreciseNumber::new(amount_scaled as u128).unwrap();
-        msg!("amount_precise: {:?}", amount_precise);
+        let amount_precise =
+            PreciseNumber::new(amount_scaled as u128).ok_or(VRGDAError::MathOverflow)?;
+        trace!("amount_precise: {:?}", amount_precise);
         //sum of m terms: p_s1 * (q^m - 1) / (q - 1)
-        let q_pow_m = q.pow(&amount_precise).unwrap();
-        msg!("Q_POW_M: {:?}", q_pow_m);
+        let q_pow_m = q.pow(&amount_precise).ok_or(VRGDAError::PowError)?;
+        trace!("Q_POW_M: {:?}", q_pow_m);
 
+        // q > 1 for any valid decay constant, so q^m ≥ 1.
         let numerator = p_s1
-            .checked_mul(&q_pow_m.checked_sub(&PreciseNumber::one()).unwrap())
-            .unwrap();
-        let denom = q.checked_sub(&PreciseNumber::one()).unwrap();
+            .checked_mul(
+                &q_pow_m
+                    .checked_sub(&PreciseNumber::one())
+                    .ok_or(VRGDAError::PowError)?,
+            )
+            .ok_or(VRGDAError::PriceOverflow)?;
+        let denom = q
+            .checked_sub(&PreciseNumber::one())
+            .ok_or(VRGDAError::InvalidDecayConstant)?;
         let total_cost = numerator
             .checked_div(&denom)
             .ok_or(VRGDAError::DivisionError)?;
-        msg!("total_cost: {:?}", total_cost);
-
-        Ok(total_cost)
-    }
-
-    /// Super experimental O(amount) cost for buying `amount` tokens starting from `sold`
-    /// This is a chunked geometric series sum, so it should be O(1) in practice.
-    /// It is not recommended to use this function in production, as it is not optimized.
-    /// Use `vrgda_price_for_amount` instead.
-    /// This function is only for testing purposes and may not be accurate.
-    pub fn vrgda_price_for_amount_v1(
-        &self,
-        now: i64,
-        sold: u64,
-        amount: u64,
-    ) -> VrgdaResult<PreciseNumber> {
-        msg!("now: {:?}", now);
-        msg!("start timestamp: {:?}", self.vrgda_start_timestamp);
-        
-        // seconds to minutes conver
-        let elapsed_wad = PreciseNumber::new((now - self.vrgda_start_timestamp) as u128)
-            .ok_or(VRGDAError::MathOverflow)?;
-
-        msg!("ELAPSED TIME in seconds: {:?}", elapsed_wad);
-        let seconds_per_minute = PreciseNumber::new(60u128).unwrap();
-        let elapsed_minutes = elapsed_wad.checked_div(&seconds_per_minute).unwrap();
-
-        let scaled_sold = sold.checked_div(1_000_000).unwrap();
-
-        let amount_scaled = amount.checked_div(1_000_000).unwrap();
-
-        let f_inv_wad = self.get_target_sale_time_precise(scaled_sold + amount_scaled);
-        msg!("f_inv_wad: {:?}", f_inv_wad);
-
-        // normalized (t − S/r) = (elapsed_wad − f_inv_wad) / ONE_PREC
-        let t_minus_sr = elapsed_minutes
-            .signed()
-            .checked_sub(&f_inv_wad.signed())
-            .unwrap()
-            .checked_div(&ONE_PREC.signed())
-            .ok_or(VRGDAError::DivisionError)?;
-        msg!("t_minus_sr: {:?}", t_minus_sr);
-
-        // decay fraction k = (decay_constant_percent / 100) in wad
-        let k_wad = PreciseNumber::new(self.decay_constant_percent as u128)
-            .unwrap()
-            // .checked_mul(&ONE_PREC).unwrap()
-            .checked_div(&PreciseNumber::new(100).unwrap())
-            .unwrap();
-        msg!("k_wad: {:?}", k_wad);
-
-        let one_minus_k = ONE_PREC
-            .checked_sub(&k_wad)
-            .ok_or(VRGDAError::OneMinusKError)?;
-        msg!("one_minus_k: {:?}", one_minus_k);
-
-        //  // *** FIX: Normalize one_minus_k by dividing by ONE_PREC so that the log function gets a number < 1.
-        // let normalized_one_minus_k = one_minus_k
-        //     .checked_div(&PreciseNumber::new(ONE).unwrap())
-        //     .unwrap();
-        // msg!("Normalized ONE MINUS K: {:?}", normalized_one_minus_k);
-
-        let ln1k = one_minus_k.log().ok_or(VRGDAError::LogError)?;
-        if !ln1k.is_negative {
-            return Err(VRGDAError::LogError);
-        }
-
-        msg!("ln1k: {:?}", ln1k);
+        trace!("total_cost: {:?}", total_cost);
 
-        let p0 = PreciseNumber {
-            value: InnerUint::from(self.target_price as u128),
-        };
-
-        msg!("p0: {:?}", p0);
-        let next_mul = ln1k
-            .checked_mul(&t_minus_sr)
-            .ok_or(VRGDAError::ExponentError)?
-            .exp()
-            .ok_or(VRGDAError::ExponentTooLarge)?;
-        let p_s1 = p0.checked_mul(&next_mul).unwrap();
-        msg!("p_s1: {:?}", p_s1);
-
-        let r = match self.schedule {
-            Schedule::LinearSchedule { r } => r as u128,
-            _ => unreachable!(),
-        };
-        let inv_r = PreciseNumber::one()
-            .checked_div(&PreciseNumber::new(r).unwrap())
-            .unwrap()
-            .signed();
-
-        msg!("inv_r: {:?}", inv_r);
-        let neg_ln1k = SignedPreciseNumber {
-            value: ln1k.value.clone(),
-            is_negative: !ln1k.is_negative,
-        };
-
-        msg!("neg_ln1k: {:?}", neg_ln1k);
-
-        let q = neg_ln1k
-            .checked_mul(&inv_r)
-            .ok_or(VRGDAError::ExponentError)?
-            .exp()
-            .ok_or(VRGDAError::ExponentTooLarge)?;
-        msg!("q: {:?}", q);
-        // ───────────────────────────────────────────────────────────────────────────
-        // Chunked geometric‐series sum so q^m never overflows:
-        //    sum_{i=0..amount−1} p_s1 * q^i
-        let mut remaining = amount;
-        let mut current_price = p_s1.clone();
-        let mut total_cost = PreciseNumber::zero();
-        let one = PreciseNumber::one();
-
-        while remaining > 0 {
-            // take at most r tokens per chunk
-            let chunk = remaining.min(r.try_into().unwrap());
-            remaining -= chunk;
-
-            // convert chunk (u64) into your wad‐supply units:
-            // PreciseNumber::precise_supply_amt(x, mint_decimals)
-            // for a 6‐decimals mint this is x * 10^(18−6)
-            let chunk_precise =
-                PreciseNumber::precise_supply_amt(chunk, /* your mint.decimals */ 6);
-            msg!("chunk_precise: {:?}", chunk_precise);
-
-            // one exponentiation per chunk:
-            let q_pow = q.pow(&chunk_precise).ok_or(VRGDAError::ExponentTooLarge)?;
-            msg!("q_pow: {:?}", q_pow);
-
-            // batch_cost = current_price * (q^chunk − 1) / (q − 1)
-            let numerator = q_pow.checked_sub(&one).unwrap();
-            let denominator = q.checked_sub(&one).unwrap();
-            let batch_cost = current_price
-                .checked_mul(&numerator)
-                .unwrap()
-                .checked_div(&denominator)
-                .ok_or(VRGDAError::DivisionError)?;
-            msg!("batch_cost: {:?}", batch_cost);
-
-            total_cost = total_cost.checked_add(&batch_cost).unwrap();
-
-            // advance current_price by chunk:
-            current_price = current_price.checked_mul(&q_pow).unwrap();
-        }
-
-        msg!("total_cost: {:?}", total_cost);
         Ok(total_cost)
     }
 }
 
-pub fn get_target_sale_time_precise_for_test(n: u64, r: u64) -> PreciseNumber {
+pub fn get_target_sale_time_precise_for_test(n: u64, r: u64) -> VrgdaResult<PreciseNumber> {
     // n_wad = n * 10^18
     let n_wad = PreciseNumber {
         value: InnerUint::from(n as u128),
@@ -575,9 +1188,7 @@ pub fn get_target_sale_time_precise_for_test(n: u64, r: u64) -> PreciseNumber {
         value: InnerUint::from(r as u128),
     };
     // t* = n_wad / r_wad
-    n_wad
-        .checked_div(&r_wad)
-        .expect("division by zero in get_target_sale_time_precise")
+    n_wad.checked_div(&r_wad).ok_or(VRGDAError::InvalidSchedule)
 }
 
 pub fn vrgda_price_for_amount_for_tests(
@@ -588,49 +1199,59 @@ pub fn vrgda_price_for_amount_for_tests(
     r: u64,
     decay_constant_percent: u8,
     target_price: u64,
-) -> PreciseNumber {
+) -> VrgdaResult<PreciseNumber> {
+    let elapsed_seconds = now
+        .checked_sub(start_ts)
+        .filter(|elapsed| *elapsed >= 0)
+        .ok_or(VRGDAError::AuctionNotStarted)? as u128;
+
     // elapsed = now - start_ts, in WAD
-    let elapsed = PreciseNumber::new((now - start_ts) as u128)
-        .expect("overflow in elapsed time")
-        .checked_div(&ONE_PREC)
-        .unwrap();
+    let elapsed = PreciseNumber::new(elapsed_seconds)
+        .and_then(|elapsed| elapsed.checked_div(&ONE_PREC))
+        .ok_or(VRGDAError::MathOverflow)?;
 
     // scale sold down if you used micro‐units originally
-    let scaled_sold = (sold / 1_000_000) as u64;
+    let scaled_sold = sold / 1_000_000;
 
     // rt = ideal tokens sold by now = (now - start_ts) * r
-    let rt = ((now - start_ts) as u128).saturating_mul(r as u128) as u64;
+    let rt = elapsed_seconds.saturating_mul(r as u128) as u64;
 
     // f_inv_wad = t* for the next token index
     let f_inv_wad = if sold == 0 {
-        get_target_sale_time_precise_for_test(scaled_sold.min(rt), r)
+        get_target_sale_time_precise_for_test(scaled_sold.min(rt), r)?
     } else {
-        get_target_sale_time_precise_for_test(scaled_sold + 1, r)
+        get_target_sale_time_precise_for_test(scaled_sold + 1, r)?
     };
 
     // t_minus_sr = elapsed - f_inv_wad, back in seconds
     let t_minus_sr = elapsed
         .signed()
         .checked_sub(&f_inv_wad.signed())
-        .unwrap()
+        .ok_or(VRGDAError::ExponentErrorInTMinusFInverse)?
         .checked_div(&ONE_PREC.signed())
-        .unwrap();
+        .ok_or(VRGDAError::DivisionError)?;
 
     // k_wad = decay_constant_percent / 100, in WAD
     let k_wad = PreciseNumber::new(decay_constant_percent as u128)
-        .unwrap()
-        .checked_div(&PreciseNumber::new(100).unwrap())
-        .unwrap();
-
-    let one_minus_k = ONE_PREC.checked_sub(&k_wad).unwrap();
-    let ln1k = one_minus_k.log().expect("log(1−k) failure");
-    assert!(ln1k.is_negative, "ln(1−k) must be negative");
+        .and_then(|k| k.checked_div(&PreciseNumber::new(100)?))
+        .ok_or(VRGDAError::DivisionError)?;
+
+    let one_minus_k = ONE_PREC
+        .checked_sub(&k_wad)
+        .ok_or(VRGDAError::OneMinusKError)?;
+    let ln1k = one_minus_k.log().ok_or(VRGDAError::LogError)?;
+    // ln(1−k) must be negative
+    if !ln1k.is_negative {
+        return Err(VRGDAError::LogError);
+    }
 
     // raw exponent = ln(1−k) * (t − S/r)
-    let raw_exp = ln1k.checked_mul(&t_minus_sr).unwrap();
+    let raw_exp = ln1k
+        .checked_mul(&t_minus_sr)
+        .ok_or(VRGDAError::ExponentError)?;
 
     // next_mul = exp(raw_exp)
-    let next_mul = raw_exp.exp().expect("exp overflow");
+    let next_mul = raw_exp.exp().ok_or(VRGDAError::ExponentTooLarge)?;
 
     // p₀ in WAD
     let p0 = PreciseNumber {
@@ -638,26 +1259,35 @@ pub fn vrgda_price_for_amount_for_tests(
     };
 
     // price for the very next token
-    let p_s1 = p0.checked_mul(&next_mul).unwrap();
+    let p_s1 = p0.checked_mul(&next_mul).ok_or(VRGDAError::PriceOverflow)?;
 
     // q = (1−k)^(−1/r)
-    let inv_r = PreciseNumber::one()
-        .checked_div(&PreciseNumber::new(r as u128).unwrap())
-        .unwrap()
+    let inv_r = PreciseNumber::new(r as u128)
+        .and_then(|r| PreciseNumber::one().checked_div(&r))
+        .ok_or(VRGDAError::InvalidSchedule)?
         .signed();
     let neg_ln1k = SignedPreciseNumber {
         value: ln1k.value.clone(),
         is_negative: !ln1k.is_negative,
     };
-    let q = neg_ln1k.checked_mul(&inv_r).unwrap().exp().unwrap();
+    let q = neg_ln1k
+        .checked_mul(&inv_r)
+        .and_then(|exponent| exponent.exp())
+        .ok_or(VRGDAError::ExponentTooLarge)?;
 
     // sum of m terms: p_s1 * (q^m − 1) / (q − 1)
-    let amt_wad = PreciseNumber::new((amount / 1_000_000) as u128).unwrap();
-    let q_pow_m = q.pow(&amt_wad).unwrap();
-    let numerator = p_s1
-        .checked_mul(&q_pow_m.checked_sub(&PreciseNumber::one()).unwrap())
-        .unwrap();
-    let denominator = q.checked_sub(&PreciseNumber::one()).unwrap();
-
-    numerator.checked_div(&denominator).unwrap()
+    let amt_wad =
+        PreciseNumber::new((amount / 1_000_000) as u128).ok_or(VRGDAError::MathOverflow)?;
+    let numerator = q
+        .pow(&amt_wad)
+        .and_then(|q_pow_m| q_pow_m.checked_sub(&PreciseNumber::one()))
+        .and_then(|growth| p_s1.checked_mul(&growth))
+        .ok_or(VRGDAError::PriceOverflow)?;
+    let denominator = q
+        .checked_sub(&PreciseNumber::one())
+        .ok_or(VRGDAError::DivisionError)?;
+
+    numerator
+        .checked_div(&denominator)
+        .ok_or(VRGDAError::DivisionError)
 }